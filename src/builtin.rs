@@ -0,0 +1,211 @@
+use std::io::Write;
+use crate::parser::ast::Type;
+use crate::vm::error::ErrorKind;
+use crate::vm::value::Value;
+
+/// A function provided by the language for output and conversions, which
+/// every program can call.
+///
+/// Programs can't define functions with the same names. The other functions
+/// of the language are [native functions](crate::strings::natives).
+///
+/// Builtins aren't native functions, because `print` and `println` write to
+/// the output of the running program, and they and `to_string` accept a value
+/// of any type, which a native signature can't express. The RISC-V compiler
+/// also implements them in assembly. Each pass looks up the signatures here
+/// instead.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Builtin {
+    /// Write a value to the output.
+    Print,
+    /// Write a value to the output, followed by a newline.
+    Println,
+    /// A value as `print` writes it.
+    ToString,
+    /// A decimal integer with an optional sign.
+    ParseInt,
+    /// `true` or `false`.
+    ParseBool,
+}
+
+impl Builtin {
+    pub const ALL: [Builtin; 5] = [
+        Builtin::Print,
+        Builtin::Println,
+        Builtin::ToString,
+        Builtin::ParseInt,
+        Builtin::ParseBool,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Print => "print",
+            Self::Println => "println",
+            Self::ToString => "to_string",
+            Self::ParseInt => "parse_int",
+            Self::ParseBool => "parse_bool",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Builtin> {
+        Self::ALL.into_iter().find(|builtin| builtin.name() == name)
+    }
+
+    /// The types of the parameters, where `None` accepts a value of any type.
+    pub fn parameters(&self) -> &'static [Option<Type>] {
+        match self {
+            Self::Print | Self::Println | Self::ToString => &[None],
+            Self::ParseInt | Self::ParseBool => &[Some(Type::String)],
+        }
+    }
+
+    pub fn return_type(&self) -> Option<Type> {
+        match self {
+            Self::Print | Self::Println => None,
+            Self::ParseInt => Some(Type::Int),
+            Self::ParseBool => Some(Type::Bool),
+            Self::ToString => Some(Type::String),
+        }
+    }
+
+    /// An estimate of the bytes of the string the builtin builds from the
+    /// arguments, so that it can be checked against the limits before
+    /// building it.
+    pub fn string_bytes(&self, args: &[Value]) -> usize {
+        match (self, args) {
+            (Self::ToString, [value @ (Value::Int(_) | Value::Bool(_))]) => value.to_string().len(),
+            _ => 0,
+        }
+    }
+
+    /// Check the arguments and run the builtin, writing to the output.
+    pub fn call(&self, args: &[Value], output: &mut dyn Write) -> Result<Option<Value>, ErrorKind> {
+        let parameters = self.parameters();
+
+        if parameters.len() != args.len() {
+            return Err(ErrorKind::Arity {
+                function: self.name().into(),
+                expected: parameters.len(),
+                found: args.len(),
+            });
+        }
+
+        for (index, (expected, value)) in parameters.iter().zip(args).enumerate() {
+            match expected {
+                Some(expected) if *expected != value.ast_type() => {
+                    return Err(ErrorKind::TypeMismatch {
+                        context: format!("argument {} of function {}", index + 1, self.name()),
+                        expected: *expected,
+                        found: value.ast_type(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let value = match (self, args) {
+            (Self::Print, [value]) => {
+                write!(output, "{value}").map_err(|error| ErrorKind::Output(error.to_string()))?;
+                return Ok(None);
+            }
+            (Self::Println, [value]) => {
+                writeln!(output, "{value}").map_err(|error| ErrorKind::Output(error.to_string()))?;
+                return Ok(None);
+            }
+            (Self::ToString, [value @ Value::String(_)]) => value.clone(),
+            (Self::ToString, [value]) => Value::String(value.to_string().into()),
+            (Self::ParseInt, [Value::String(text)]) => {
+                Value::Int(text.parse().map_err(|_| ErrorKind::Parse { text: text.to_string(), expected: Type::Int })?)
+            }
+            (Self::ParseBool, [Value::String(text)]) => match &**text {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ => Err(ErrorKind::Parse { text: text.to_string(), expected: Type::Bool })?,
+            },
+            _ => unreachable!("The arguments are checked"),
+        };
+
+        Ok(Some(value))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::ast::Type;
+    use crate::vm::error::ErrorKind;
+    use crate::vm::value::Value;
+    use super::Builtin;
+
+    #[test]
+    fn test_print() {
+        let mut output = vec![];
+
+        Builtin::Print.call(&[Value::String("a".into())], &mut output).unwrap();
+        Builtin::Print.call(&[Value::Int(-12)], &mut output).unwrap();
+        Builtin::Println.call(&[Value::Bool(true)], &mut output).unwrap();
+        Builtin::Println.call(&[Value::Bool(false)], &mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "a-12true\nfalse\n");
+
+        assert_eq!(
+            Builtin::Print.call(&[], &mut vec![]),
+            Err(ErrorKind::Arity { function: "print".into(), expected: 1, found: 0 }),
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        for builtin in Builtin::ALL {
+            assert_eq!(Builtin::from_name(builtin.name()), Some(builtin));
+        }
+
+        assert_eq!(Builtin::from_name("main"), None);
+    }
+
+    fn call(builtin: Builtin, args: &[Value]) -> Result<Option<Value>, ErrorKind> {
+        builtin.call(args, &mut vec![])
+    }
+
+    fn string(text: &str) -> Value {
+        Value::String(text.into())
+    }
+
+    #[test]
+    fn test_conversions() {
+        let cases = [
+            (Builtin::ToString, vec![Value::Int(i64::MIN)], string("-9223372036854775808").into()),
+            (Builtin::ToString, vec![Value::Bool(false)], string("false").into()),
+            (Builtin::ToString, vec![string("text")], string("text").into()),
+            (Builtin::ParseInt, vec![string("-42")], Some(Value::Int(-42))),
+            (Builtin::ParseInt, vec![string("+7")], Some(Value::Int(7))),
+            (Builtin::ParseInt, vec![string("9223372036854775807")], Some(Value::Int(i64::MAX))),
+            (Builtin::ParseInt, vec![string("-9223372036854775808")], Some(Value::Int(i64::MIN))),
+            (Builtin::ParseBool, vec![string("true")], Value::Bool(true).into()),
+            (Builtin::ParseBool, vec![string("false")], Value::Bool(false).into()),
+        ];
+
+        for (builtin, args, expected) in cases {
+            assert_eq!(call(builtin, &args), Ok(expected), "{} {args:?}", builtin.name());
+        }
+    }
+
+    #[test]
+    fn test_conversion_errors() {
+        let cases = [
+            (Builtin::ParseInt, vec![string("")], ErrorKind::Parse { text: "".into(), expected: Type::Int }),
+            (Builtin::ParseInt, vec![string(" 1")], ErrorKind::Parse { text: " 1".into(), expected: Type::Int }),
+            (Builtin::ParseInt, vec![string("-")], ErrorKind::Parse { text: "-".into(), expected: Type::Int }),
+            (Builtin::ParseInt, vec![string("9223372036854775808")], ErrorKind::Parse { text: "9223372036854775808".into(), expected: Type::Int }),
+            (Builtin::ParseBool, vec![string("True")], ErrorKind::Parse { text: "True".into(), expected: Type::Bool }),
+            (Builtin::ParseInt, vec![Value::Int(1)], ErrorKind::TypeMismatch {
+                context: "argument 1 of function parse_int".into(),
+                expected: Type::String,
+                found: Type::Int,
+            }),
+        ];
+
+        for (builtin, args, expected) in cases {
+            assert_eq!(call(builtin, &args), Err(expected), "{} {args:?}", builtin.name());
+        }
+    }
+}
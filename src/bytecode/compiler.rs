@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+use crate::builtin::Builtin;
+use crate::parser::ast::{BinaryVerb, BlockNode, Expression, ExpressionNode, FunctionNode, Program, Span, Statement, TermNode, Variable};
+use crate::vm::error::ErrorKind;
+use crate::vm::native::NativeFunction;
+use crate::vm::value::Value;
+use super::instruction::{Function, Instruction, Module};
+
+/// Compile a resolved program to bytecode, which can call the string functions.
+#[cfg(test)]
+pub fn compile(program: &Program) -> Result<Module, String> {
+    compile_with_natives(program, &crate::strings::natives())
+}
+
+/// Compile a resolved program that can also call the given native functions.
+/// The module keeps the natives, so that the machine can call them.
+pub fn compile_with_natives(program: &Program, natives: &[NativeFunction]) -> Result<Module, String> {
+    // Like in the tree-walker, a later function replaces an earlier one with the same name
+    let functions: HashMap<&str, usize> = program.nodes
+        .iter()
+        .enumerate()
+        .map(|(i, function)| (function.name.as_str(), i))
+        .collect();
+
+    let native_indexes: HashMap<&str, usize> = natives
+        .iter()
+        .enumerate()
+        .map(|(i, native)| (native.name(), i))
+        .collect();
+
+    let functions = program.nodes
+        .iter()
+        .map(|function| FunctionCompiler::new(function, &functions, &native_indexes).compile(function))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Module { functions, natives: natives.to_vec() })
+}
+
+struct Loop {
+    /// Where `continue` jumps to.
+    start: usize,
+    /// The jumps of the `break` statements, to be patched with the end of the loop.
+    breaks: Vec<usize>,
+}
+
+struct FunctionCompiler<'a> {
+    functions: &'a HashMap<&'a str, usize>,
+    natives: &'a HashMap<&'a str, usize>,
+    output: Function,
+    loops: Vec<Loop>,
+}
+
+impl<'a> FunctionCompiler<'a> {
+    fn new(function: &FunctionNode, functions: &'a HashMap<&'a str, usize>, natives: &'a HashMap<&'a str, usize>) -> Self {
+        FunctionCompiler {
+            functions,
+            natives,
+            output: Function {
+                name: function.name.clone(),
+                parameters: function.parameters
+                    .iter()
+                    .map(|param| (param.name.clone(), param.type_name))
+                    .collect(),
+                return_type: function.return_type,
+                slots: function.parameters.iter().map(|param| param.name.clone()).collect(),
+                code: vec![],
+                spans: vec![],
+                constants: vec![],
+                span: function.span,
+            },
+            loops: vec![],
+        }
+    }
+
+    fn compile(mut self, function: &FunctionNode) -> Result<Function, String> {
+        self.compile_block(&function.block)?;
+        self.emit(Instruction::ReturnNone, function.span);
+
+        Ok(self.output)
+    }
+
+    /// Add an instruction, and return its index.
+    fn emit(&mut self, instruction: Instruction, span: Span) -> usize {
+        self.output.code.push(instruction);
+        self.output.spans.push(span);
+        self.output.code.len() - 1
+    }
+
+    /// Point the jump at the given index to the next instruction.
+    fn patch(&mut self, jump: usize) {
+        let next = self.output.code.len();
+
+        match &mut self.output.code[jump] {
+            Instruction::Jump(target)
+            | Instruction::JumpIfFalse { target, .. }
+            | Instruction::LogicLeft { end: target, .. } => *target = next,
+            instruction => panic!("Tried to patch {instruction:?}, which is not a jump"),
+        }
+    }
+
+    fn constant(&mut self, value: Value) -> usize {
+        match self.output.constants.iter().position(|constant| *constant == value) {
+            Some(index) => index,
+            None => {
+                self.output.constants.push(value);
+                self.output.constants.len() - 1
+            }
+        }
+    }
+
+    fn slot(&mut self, variable: &Variable) -> Result<usize, String> {
+        let slot = variable.slot.ok_or(format!("Variable {variable} has not been resolved"))?;
+
+        if self.output.slots.len() <= slot {
+            self.output.slots.resize(slot + 1, String::new());
+        }
+
+        self.output.slots[slot].clone_from(&variable.name);
+
+        Ok(slot)
+    }
+
+    fn compile_block(&mut self, block: &[Statement]) -> Result<(), String> {
+        for node in block {
+            self.emit(Instruction::Statement, node.span);
+            self.compile_statement(node)?;
+        }
+
+        Ok(())
+    }
+
+    fn compile_statement(&mut self, node: &Statement) -> Result<(), String> {
+        match &node.node {
+            BlockNode::VariableDefinition { name, type_name, value } => {
+                self.compile_expression(value)?;
+                let slot = self.slot(name)?;
+                self.emit(Instruction::Define { slot, type_name: *type_name }, node.span);
+            }
+            BlockNode::Assignment { lhs, rhs } => {
+                self.compile_expression(rhs)?;
+                let slot = self.slot(lhs)?;
+                self.emit(Instruction::Store(slot), node.span);
+            }
+            BlockNode::Expression(Expression { node: ExpressionNode::Call { name, args }, span }) => {
+                // Calls to functions without a return value are allowed as statements
+                self.compile_call(name, args, true, *span)?;
+            }
+            BlockNode::Expression(expr) => {
+                self.compile_expression(expr)?;
+                self.emit(Instruction::Pop, node.span);
+            }
+            BlockNode::Block(nested) => self.compile_block(nested)?,
+            BlockNode::IfStatement { condition, block, else_block } => {
+                self.compile_expression(condition)?;
+                let skip_block = self.emit(Instruction::JumpIfFalse { target: 0, statement: "if-statement" }, condition.span);
+                self.compile_block(block)?;
+
+                if let Some(else_block) = else_block {
+                    let skip_else = self.emit(Instruction::Jump(0), node.span);
+                    self.patch(skip_block);
+                    self.compile_block(else_block)?;
+                    self.patch(skip_else);
+                } else {
+                    self.patch(skip_block);
+                }
+            }
+            BlockNode::WhileLoop { condition, block } => {
+                // Loops with an empty body have to run out of statements as well
+                let start = self.emit(Instruction::Statement, condition.span);
+                self.compile_expression(condition)?;
+                let exit = self.emit(Instruction::JumpIfFalse { target: 0, statement: "while-loop" }, condition.span);
+
+                self.loops.push(Loop { start, breaks: vec![] });
+                self.compile_block(block)?;
+                let current = self.loops.pop().expect("Loop stack is empty");
+
+                self.emit(Instruction::Jump(start), node.span);
+                self.patch(exit);
+
+                for jump in current.breaks {
+                    self.patch(jump);
+                }
+            }
+            BlockNode::Break => {
+                if self.loops.is_empty() {
+                    self.emit(Instruction::Fail(ErrorKind::BreakOutsideLoop), self.output.span);
+                } else {
+                    let jump = self.emit(Instruction::Jump(0), node.span);
+                    self.loops.last_mut().expect("Loop stack is empty").breaks.push(jump);
+                }
+            }
+            BlockNode::Continue => {
+                match self.loops.last() {
+                    Some(current) => self.emit(Instruction::Jump(current.start), node.span),
+                    None => self.emit(Instruction::Fail(ErrorKind::BreakOutsideLoop), self.output.span),
+                };
+            }
+            BlockNode::Return(expr) => {
+                self.compile_expression(expr)?;
+                // Errors about the return value are reported at the function, like in the tree-walker
+                self.emit(Instruction::Return, self.output.span);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn compile_expression(&mut self, expr: &Expression) -> Result<(), String> {
+        match &expr.node {
+            ExpressionNode::BinaryOperation { verb: verb @ (BinaryVerb::And | BinaryVerb::Or), lhs, rhs } => {
+                self.compile_expression(lhs)?;
+                let skip_rhs = self.emit(Instruction::LogicLeft { verb: verb.clone(), end: 0 }, expr.span);
+                self.compile_expression(rhs)?;
+                self.emit(Instruction::LogicRight(verb.clone()), expr.span);
+                self.patch(skip_rhs);
+            }
+            ExpressionNode::BinaryOperation { verb, lhs, rhs } => {
+                self.compile_expression(lhs)?;
+                self.compile_expression(rhs)?;
+                self.emit(Instruction::Binary(verb.clone()), expr.span);
+            }
+            ExpressionNode::Unary { op, operand } => {
+                self.compile_expression(operand)?;
+                self.emit(Instruction::Unary(op.clone()), expr.span);
+            }
+            ExpressionNode::Call { name, args } => self.compile_call(name, args, false, expr.span)?,
+            ExpressionNode::Term(TermNode::Variable(variable)) => {
+                let slot = self.slot(variable)?;
+                self.emit(Instruction::Load(slot), expr.span);
+            }
+            ExpressionNode::Term(term) => {
+                let value = match term {
+                    TermNode::Boolean(x) => Value::Bool(*x),
+                    TermNode::Integer(x) => Value::Int(*x),
+                    TermNode::String(x) => Value::String(x.as_str().into()),
+                    TermNode::Variable(_) => unreachable!("Variables are loaded above"),
+                };
+
+                let constant = self.constant(value);
+                self.emit(Instruction::Constant(constant), expr.span);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn compile_call(&mut self, name: &str, args: &[Expression], discard: bool, span: Span) -> Result<(), String> {
+        for arg in args {
+            self.compile_expression(arg)?;
+        }
+
+        if let Some(function) = self.functions.get(name) {
+            self.emit(Instruction::Call { function: *function, args: args.len(), discard }, span);
+        } else if let Some(native) = self.natives.get(name) {
+            self.emit(Instruction::CallNative { native: *native, args: args.len(), discard }, span);
+        } else if let Some(builtin) = Builtin::from_name(name) {
+            self.emit(Instruction::CallBuiltin { builtin, args: args.len(), discard }, span);
+        } else {
+            Err(format!("Undefined function {name}"))?
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::ast::{BinaryVerb, Type};
+    use crate::parser::parse;
+    use crate::resolver::{resolve, resolve_with_natives};
+    use crate::vm::error::ErrorKind;
+    use crate::vm::function::Functions;
+    use crate::vm::native::NativeFunction;
+    use crate::vm::value::Value;
+    use crate::vm::{self, Limits};
+    use super::{compile, compile_with_natives};
+    use super::super::execute;
+    use super::super::instruction::Instruction;
+
+    #[test]
+    fn test_while_loop() {
+        let mut program = parse("
+            function main() -> int {
+                var x: int = 0;
+
+                while (x < 10) {
+                    x = x + 1;
+                }
+
+                return x;
+            }
+        ").expect("Could not parse program");
+        resolve(&mut program).expect("Could not resolve program");
+
+        let module = compile(&program).expect("Could not compile program");
+        let main = &module.functions[0];
+
+        assert_eq!(main.constants, vec![Value::Int(0), Value::Int(10), Value::Int(1)]);
+        assert_eq!(
+            main.code,
+            vec![
+                Instruction::Statement,
+                Instruction::Constant(0),
+                Instruction::Define { slot: 0, type_name: Type::Int },
+                Instruction::Statement,
+                // The check of the loop condition counts as a statement
+                Instruction::Statement,
+                Instruction::Load(0),
+                Instruction::Constant(1),
+                Instruction::Binary(BinaryVerb::Less),
+                Instruction::JumpIfFalse { target: 15, statement: "while-loop" },
+                Instruction::Statement,
+                Instruction::Load(0),
+                Instruction::Constant(2),
+                Instruction::Binary(BinaryVerb::Plus),
+                Instruction::Store(0),
+                Instruction::Jump(4),
+                Instruction::Statement,
+                Instruction::Load(0),
+                Instruction::Return,
+                Instruction::ReturnNone,
+            ],
+        );
+        assert_eq!(main.slots, vec!["x".to_string()]);
+    }
+
+    #[test]
+    fn test_natives() {
+        let natives = vec![
+            NativeFunction::new("add", vec![Type::Int, Type::Int], Some(Type::Int), |args| match args {
+                [Value::Int(x), Value::Int(y)] => Ok(Some(Value::Int(x + y))),
+                _ => unreachable!("The arguments are checked"),
+            }),
+            NativeFunction::new("fail", vec![], None, |_| Err(ErrorKind::Native("Out of coffee".into()))),
+        ];
+        let signatures: Vec<_> = natives.iter().map(|native| native.signature.clone()).collect();
+
+        let mut program = parse("
+            function main() -> int {
+                var total: int = add(1, 2);
+                if (total == 3) {
+                    fail();
+                }
+                return total;
+            }
+
+            function sum() -> int {
+                return add(add(1, 2), 7);
+            }
+        ").expect("Could not parse program");
+        resolve_with_natives(&mut program, &signatures).expect("Could not resolve program");
+
+        compile(&program).expect_err("The natives are not known without passing them");
+
+        let module = compile_with_natives(&program, &natives).expect("Could not compile program");
+        let sum = module.function_index("sum").expect("sum should be compiled");
+        assert!(module.functions[sum].code.contains(&Instruction::CallNative { native: 0, args: 2, discard: false }));
+
+        // Errors of natives are at the call, like in the tree-walker
+        let error = execute(&module).expect_err("The native should fail");
+        let functions = Functions::new(&program, &natives, &Limits::default());
+        let expected = vm::call(&functions, "main", vec![], Limits::default(), &mut std::io::sink())
+            .expect_err("The native should fail");
+        assert_eq!(error, expected);
+        assert_eq!(error.kind, ErrorKind::Native("Out of coffee".into()));
+        let span = error.span.expect("The error should have a location");
+        assert_eq!((span.line, span.column), (5, 21));
+        assert_eq!(error.backtrace, vec!["main".to_string()]);
+    }
+}
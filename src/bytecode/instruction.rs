@@ -0,0 +1,78 @@
+use crate::builtin::Builtin;
+use crate::parser::ast::{BinaryVerb, Ident, Span, Type, UnaryVerb};
+use crate::vm::error::ErrorKind;
+use crate::vm::native::NativeFunction;
+use crate::vm::value::Value;
+
+/// A single instruction for the stack machine.
+///
+/// Instructions take their operands from the top of the value stack, and
+/// push their result back on it. Jump targets are indexes into the code of
+/// the function.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Instruction {
+    /// Count a statement against the limits, before running it or checking a loop condition.
+    Statement,
+    /// Push a value from the constant pool.
+    Constant(usize),
+    /// Push the value of a local variable.
+    Load(usize),
+    /// Pop a value into a new local variable, checking that it has the declared type.
+    Define { slot: usize, type_name: Type },
+    /// Pop a value into an existing local variable, checking that it keeps its type.
+    Store(usize),
+    /// Discard the value on the top of the stack.
+    Pop,
+    Binary(BinaryVerb),
+    Unary(UnaryVerb),
+    /// Check that the left operand of `&&` or `||` is a bool. If it decides
+    /// the result, leave it as the result and jump to `end`, otherwise pop it.
+    LogicLeft { verb: BinaryVerb, end: usize },
+    /// Check that the right operand of `&&` or `||` is a bool.
+    LogicRight(BinaryVerb),
+    Jump(usize),
+    /// Pop the condition of a statement, and jump if it is false.
+    JumpIfFalse { target: usize, statement: &'static str },
+    /// Pop the arguments and call the function. The result is pushed, unless it is discarded.
+    Call { function: usize, args: usize, discard: bool },
+    /// Pop the arguments and run the builtin. The result is pushed, unless it is discarded.
+    CallBuiltin { builtin: Builtin, args: usize, discard: bool },
+    /// Pop the arguments and call the native function of the module. The result is pushed, unless it is discarded.
+    CallNative { native: usize, args: usize, discard: bool },
+    /// Pop the return value, and return from the function.
+    Return,
+    /// Return from the function without a value.
+    ReturnNone,
+    /// Stop with an error.
+    Fail(ErrorKind),
+}
+
+/// A compiled function.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Function {
+    pub name: Ident,
+    pub parameters: Vec<(Ident, Type)>,
+    pub return_type: Option<Type>,
+    /// The names of the local variables, indexed by slot. The parameters use the first slots.
+    pub slots: Vec<Ident>,
+    pub code: Vec<Instruction>,
+    /// The location of the node each instruction was compiled from.
+    pub spans: Vec<Span>,
+    pub constants: Vec<Value>,
+    pub span: Span,
+}
+
+/// A compiled program.
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub functions: Vec<Function>,
+    /// The native functions the program was compiled against.
+    pub natives: Vec<NativeFunction>,
+}
+
+impl Module {
+    /// Like in the tree-walker, a later function replaces an earlier one with the same name.
+    pub fn function_index(&self, name: &str) -> Option<usize> {
+        self.functions.iter().rposition(|function| function.name == name)
+    }
+}
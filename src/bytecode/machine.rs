@@ -0,0 +1,293 @@
+use std::io::Write;
+use crate::parser::ast::BinaryVerb;
+use crate::vm::error::{ErrorKind, RuntimeError};
+use crate::vm::function::run_native;
+use crate::vm::limits::{Limits, Usage};
+use crate::vm::return_code;
+use crate::vm::value::{binary_operation, logic_operand, unary_operation, Value};
+use super::instruction::{Function, Instruction, Module};
+
+/// Run the main function of the module, and return its exit code.
+#[cfg(test)]
+pub fn execute(module: &Module) -> Result<i64, RuntimeError> {
+    execute_with_limits(module, Limits::default())
+}
+
+/// Run the main function of the module, stopping with an error when it
+/// exceeds one of the limits.
+#[cfg(test)]
+pub fn execute_with_limits(module: &Module, limits: Limits) -> Result<i64, RuntimeError> {
+    execute_with_output(module, limits, &mut std::io::stdout())
+}
+
+/// Run the main function of the module, with the builtins writing to the output.
+///
+/// The machine doesn't nest on the stack of the host, only its compiler does.
+pub fn execute_with_output(module: &Module, limits: Limits, output: &mut dyn Write) -> Result<i64, RuntimeError> {
+    let main = module.function_index("main").ok_or(ErrorKind::UndefinedFunction("main".into()))?;
+
+    let mut machine = Machine {
+        module,
+        stack: vec![],
+        frames: vec![],
+        limits,
+        usage: Usage::default(),
+        output,
+    };
+
+    Ok(return_code(machine.run(main)?)?)
+}
+
+#[derive(Copy, Clone)]
+struct Frame {
+    function: usize,
+    /// The index of the next instruction.
+    ip: usize,
+    /// Where the local variables of the function start on the value stack.
+    base: usize,
+    /// Whether the caller throws away the return value.
+    discard: bool,
+}
+
+struct Machine<'a> {
+    module: &'a Module,
+    /// The local variables of each frame, followed by its temporary values.
+    stack: Vec<Value>,
+    frames: Vec<Frame>,
+    limits: Limits,
+    usage: Usage,
+    /// Where the builtins write to.
+    output: &'a mut dyn Write,
+}
+
+impl<'a> Machine<'a> {
+    fn frame(&self) -> Frame {
+        *self.frames.last().expect("No frames on the call stack")
+    }
+
+    fn function(&self, frame: &Frame) -> &'a Function {
+        &self.module.functions[frame.function]
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("Value stack is empty")
+    }
+
+    /// An error at the instruction that is running, with the backtrace of the call stack.
+    fn error(&self, kind: ErrorKind) -> RuntimeError {
+        let frame = self.frame();
+        let mut error = RuntimeError::from(kind).at(self.function(&frame).spans[frame.ip - 1]);
+
+        for frame in self.frames.iter().rev() {
+            error = error.in_function(&self.function(frame).name);
+        }
+
+        error
+    }
+
+    /// Push a frame for the function, taking the arguments from the value stack.
+    fn call(&mut self, function: usize, args: usize, discard: bool) -> Result<(), ErrorKind> {
+        let callee = &self.module.functions[function];
+        let base = self.stack.len() - args;
+
+        if self.frames.len() >= self.limits.max_call_depth {
+            return Err(ErrorKind::CallDepthLimit(self.limits.max_call_depth));
+        }
+
+        if callee.parameters.len() != args {
+            return Err(ErrorKind::Arity {
+                function: callee.name.clone(),
+                expected: callee.parameters.len(),
+                found: args,
+            });
+        }
+
+        for ((name, type_name), value) in callee.parameters.iter().zip(&self.stack[base..]) {
+            if value.ast_type() != *type_name {
+                return Err(ErrorKind::TypeMismatch {
+                    context: format!("parameter {name} of function {}", callee.name),
+                    expected: *type_name,
+                    found: value.ast_type(),
+                });
+            }
+        }
+
+        // Placeholders for the local variables, until they are defined
+        self.stack.resize(base + callee.slots.len(), Value::Int(0));
+        self.frames.push(Frame { function, ip: 0, base, discard });
+
+        Ok(())
+    }
+
+    /// Call a function, and run until it returns.
+    fn run(&mut self, function: usize) -> Result<Option<Value>, RuntimeError> {
+        self.call(function, 0, false)?;
+
+        loop {
+            let frame = self.frame();
+            let code = &self.function(&frame).code;
+            self.frames.last_mut().expect("No frames on the call stack").ip += 1;
+
+            match &code[frame.ip] {
+                Instruction::Statement => {
+                    self.usage.statement(&self.limits).map_err(|kind| self.error(kind))?;
+                }
+                Instruction::Constant(index) => {
+                    self.stack.push(self.function(&frame).constants[*index].clone());
+                }
+                Instruction::Load(slot) => {
+                    self.stack.push(self.stack[frame.base + slot].clone());
+                }
+                Instruction::Define { slot, type_name } => {
+                    let value = self.pop();
+
+                    if value.ast_type() != *type_name {
+                        return Err(self.error(ErrorKind::TypeMismatch {
+                            context: format!("variable {}", self.function(&frame).slots[*slot]),
+                            expected: *type_name,
+                            found: value.ast_type(),
+                        }));
+                    }
+
+                    self.stack[frame.base + slot] = value;
+                }
+                Instruction::Store(slot) => {
+                    let value = self.pop();
+                    let expected = self.stack[frame.base + slot].ast_type();
+
+                    if value.ast_type() != expected {
+                        return Err(self.error(ErrorKind::TypeMismatch {
+                            context: format!("assignment to {}", self.function(&frame).slots[*slot]),
+                            expected,
+                            found: value.ast_type(),
+                        }));
+                    }
+
+                    self.stack[frame.base + slot] = value;
+                }
+                Instruction::Pop => {
+                    self.pop();
+                }
+                Instruction::Binary(verb) => {
+                    let rhs = self.pop();
+                    let lhs = self.pop();
+                    let result = binary_operation(verb, lhs, rhs).map_err(|kind| self.error(kind))?;
+
+                    if let Value::String(string) = &result {
+                        self.usage.string(&self.limits, string.len()).map_err(|kind| self.error(kind))?;
+                    }
+
+                    self.stack.push(result);
+                }
+                Instruction::Unary(op) => {
+                    let operand = self.pop();
+                    let result = unary_operation(op, operand).map_err(|kind| self.error(kind))?;
+                    self.stack.push(result);
+                }
+                Instruction::LogicLeft { verb, end } => {
+                    let value = self.pop();
+                    let lhs = logic_operand(verb, "left", value).map_err(|kind| self.error(kind))?;
+
+                    if lhs == (*verb == BinaryVerb::Or) {
+                        self.stack.push(Value::Bool(lhs));
+                        self.jump(*end);
+                    }
+                }
+                Instruction::LogicRight(verb) => {
+                    let value = self.pop();
+                    let rhs = logic_operand(verb, "right", value).map_err(|kind| self.error(kind))?;
+                    self.stack.push(Value::Bool(rhs));
+                }
+                Instruction::Jump(target) => self.jump(*target),
+                Instruction::JumpIfFalse { target, statement } => {
+                    match self.pop() {
+                        Value::Bool(true) => {}
+                        Value::Bool(false) => self.jump(*target),
+                        value => {
+                            return Err(self.error(ErrorKind::InvalidCondition { statement, found: value.ast_type() }));
+                        }
+                    }
+                }
+                Instruction::Call { function, args, discard } => {
+                    self.call(*function, *args, *discard).map_err(|kind| self.error(kind))?;
+                }
+                Instruction::CallBuiltin { builtin, args, discard } => {
+                    let args = self.stack.split_off(self.stack.len() - args);
+
+                    // Charged before building the string, so that it can't grow past the limit
+                    self.usage.string(&self.limits, builtin.string_bytes(&args)).map_err(|kind| self.error(kind))?;
+
+                    let result = builtin.call(&args, self.output).map_err(|kind| self.error(kind))?;
+                    self.push_result(builtin.name(), result, *discard)?;
+                }
+                Instruction::CallNative { native, args, discard } => {
+                    let args = self.stack.split_off(self.stack.len() - args);
+                    let native = &self.module.natives[*native];
+                    self.usage.string(&self.limits, native.string_bytes(&args)).map_err(|kind| self.error(kind))?;
+
+                    // Natives don't have a location, so their errors are at the call
+                    let result = run_native(native, args).map_err(|error| self.error(error.kind))?;
+                    self.push_result(native.name(), result, *discard)?;
+                }
+                Instruction::Return => {
+                    let value = self.pop();
+                    let function = self.function(&frame);
+
+                    match function.return_type {
+                        Some(return_type) if return_type == value.ast_type() => {}
+                        Some(return_type) => {
+                            return Err(self.error(ErrorKind::TypeMismatch {
+                                context: format!("return value of function {}", function.name),
+                                expected: return_type,
+                                found: value.ast_type(),
+                            }));
+                        }
+                        None => return Err(self.error(ErrorKind::UnexpectedReturnValue(function.name.clone()))),
+                    }
+
+                    self.stack.truncate(frame.base);
+                    self.frames.pop();
+
+                    if self.frames.is_empty() {
+                        return Ok(Some(value));
+                    } else if !frame.discard {
+                        self.stack.push(value);
+                    }
+                }
+                Instruction::ReturnNone => {
+                    let function = self.function(&frame);
+
+                    if function.return_type.is_some() {
+                        return Err(self.error(ErrorKind::MissingReturn(function.name.clone())));
+                    }
+
+                    self.stack.truncate(frame.base);
+                    self.frames.pop();
+
+                    if self.frames.is_empty() {
+                        return Ok(None);
+                    } else if !frame.discard {
+                        // The caller is running again, so the error is at the call
+                        return Err(self.error(ErrorKind::NoReturnValue(function.name.clone())));
+                    }
+                }
+                Instruction::Fail(kind) => return Err(self.error(kind.clone())),
+            }
+        }
+    }
+
+    /// Push the result of a builtin or native, unless the caller discards it.
+    fn push_result(&mut self, name: &str, result: Option<Value>, discard: bool) -> Result<(), RuntimeError> {
+        match result {
+            Some(value) if !discard => self.stack.push(value),
+            None if !discard => return Err(self.error(ErrorKind::NoReturnValue(name.into()))),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn jump(&mut self, target: usize) {
+        self.frames.last_mut().expect("No frames on the call stack").ip = target;
+    }
+}
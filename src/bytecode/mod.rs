@@ -0,0 +1,10 @@
+mod instruction;
+mod compiler;
+mod machine;
+
+pub use self::compiler::compile_with_natives;
+pub use self::machine::execute_with_output;
+#[cfg(test)]
+pub use self::compiler::compile;
+#[cfg(test)]
+pub use self::machine::{execute, execute_with_limits};
@@ -1,26 +1,77 @@
 use crate::compiler::expression::compile_expression;
 use crate::compiler::helper::store_instruction;
+use crate::compiler::label::Labels;
 use crate::compiler::stack::Stack;
-use crate::parser::ast::BlockNode;
+use crate::compiler::types::Types;
+use crate::parser::ast::{BlockNode, Statement};
 
-pub fn compile_block(block: &Vec<BlockNode>, stack: &Stack) -> Result<String, String> {
+pub fn compile_block(block: &[Statement], stack: &Stack, types: &Types, labels: &mut Labels) -> Result<String, String> {
     block.iter().map(|node| {
-        match node {
+        match &node.node {
             BlockNode::VariableDefinition { name, type_name, value } => {
-                let offset = stack.offset(&name).ok_or(format!("Could not get offset for variable {}", name))?;
+                let (offset, _) = stack.get_variable(name)?;
 
-                compile_expression(value, stack).map(|s|
-                    s + &format!("\
-    {}      t0, -{}(sp)
-", store_instruction(type_name.clone()), offset)
+                compile_expression(value, stack, types, labels).map(|s|
+                    s + &format!("    {}      t0, {}(s0)\n", store_instruction(*type_name), offset)
                 )
             }
-            BlockNode::Assignment { .. } => todo!(),
-            BlockNode::Expression(_) => todo!(),
-            BlockNode::Block(_) => todo!(),
-            BlockNode::IfStatement { .. } => todo!(),
+            BlockNode::Assignment { lhs, rhs } => {
+                let (offset, type_name) = stack.get_variable(lhs)?;
+
+                compile_expression(rhs, stack, types, labels).map(|s|
+                    s + &format!("    {}      t0, {}(s0)\n", store_instruction(type_name), offset)
+                )
+            }
+            BlockNode::Expression(expr) => compile_expression(expr, stack, types, labels),
+            BlockNode::Block(nested) => compile_block(nested, stack, types, labels),
+            BlockNode::IfStatement { condition, block, else_block } => {
+                let condition = compile_expression(condition, stack, types, labels)?;
+                let body = compile_block(block, stack, types, labels)?;
+
+                if let Some(else_block) = else_block {
+                    let else_label = labels.next("else");
+                    let end = labels.next("if_end");
+                    let else_body = compile_block(else_block, stack, types, labels)?;
+
+                    Ok(format!("\
+{condition}    beqz    t0, {else_label}
+{body}    j       {end}
+{else_label}:
+{else_body}{end}:
+"))
+                } else {
+                    let end = labels.next("if_end");
+
+                    Ok(format!("\
+{condition}    beqz    t0, {end}
+{body}{end}:
+"))
+                }
+            },
+            BlockNode::WhileLoop { condition, block } => {
+                let start = labels.next("while_start");
+                let end = labels.next("while_end");
+
+                let condition = compile_expression(condition, stack, types, labels)?;
+                let body = labels.loop_scope(&start, &end, |labels| compile_block(block, stack, types, labels))?;
+
+                Ok(format!("\
+{start}:
+{condition}    beqz    t0, {end}
+{body}    j       {start}
+{end}:
+"))
+            },
+            BlockNode::Break => {
+                let target = labels.current_loop().ok_or("Break outside of a loop")?;
+                Ok(format!("    j       {} # Break\n", target.end))
+            },
+            BlockNode::Continue => {
+                let target = labels.current_loop().ok_or("Continue outside of a loop")?;
+                Ok(format!("    j       {} # Continue\n", target.start))
+            },
             BlockNode::Return(value) => {
-                compile_expression(value, stack).map(|s|
+                compile_expression(value, stack, types, labels).map(|s|
                     s + "    mv      a0, t0 # Prepare return variable\n"
                       + "    j       0f     # Jump to end\n"
                 )
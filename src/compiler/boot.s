@@ -4,6 +4,20 @@ _BOOT_MESSAGE:
     .string "Boot complete\n"
 _EXIT_MESSAGE:
     .string "The program exited with status %d\n"
+_DIVISION_BY_ZERO_MESSAGE:
+    .string "Runtime error: Division by zero\n"
+_INTEGER_OVERFLOW_MESSAGE:
+    .string "Runtime error: Integer overflow\n"
+_TRUE:
+    .string "true"
+_FALSE:
+    .string "false"
+_PARSE_ERROR_MESSAGE:
+    .string "Runtime error: Can not parse \""
+_PARSE_INT_MESSAGE:
+    .string "\" as int\n"
+_PARSE_BOOL_MESSAGE:
+    .string "\" as bool\n"
 
 .equ    _TEST_BASE, 0x00100000
 .equ    _SHUTDOWN, 0x5555
@@ -29,12 +43,271 @@ _start:
     la      a0, _EXIT_MESSAGE
     call    printf
 
+shutdown:
     li      t0, _TEST_BASE
     li      t1, _SHUTDOWN
 1:  sw      t1, 0(t0)
     j       1b
 # Boot end
 
+# Runtime error start
+# Print the message in a0 and shut down
+.global runtime_error
+runtime_error:
+    call    printf
+    j       shutdown
+# Runtime error end
+
+# Check the operands of a division or remainder in t0 and t1, which
+# fails like in the interpreter if the result is undefined or too large.
+# Only t2 is changed.
+.global check_division
+check_division:
+    bnez    t1, 1f
+    la      a0, _DIVISION_BY_ZERO_MESSAGE
+    j       runtime_error
+1:
+    # The smallest integer divided by -1
+    li      t2, -1
+    bne     t1, t2, 2f
+    slli    t2, t2, 63
+    bne     t0, t2, 2f
+    la      a0, _INTEGER_OVERFLOW_MESSAGE
+    j       runtime_error
+2:
+    ret
+
+# Print start
+# The print and println builtins, which write through write_char
+
+# Print the null-terminated string in a0
+.global print_string
+print_string:
+    addi    sp, sp, -16
+    sd      ra, 0(sp)
+    sd      s0, 8(sp)
+
+    mv      s0, a0
+    j       2f
+1:
+    call    write_char
+    addi    s0, s0, 1
+2:
+    lbu     a0, 0(s0)
+    bnez    a0, 1b
+
+    ld      ra, 0(sp)
+    ld      s0, 8(sp)
+    addi    sp, sp, 16
+    ret
+
+# Print the signed integer in a0 in decimal
+.global print_int
+print_int:
+    addi    sp, sp, -48
+    sd      ra, 0(sp)
+
+    sb      zero, 47(sp)
+    addi    a1, sp, 47
+    call    format_int
+    call    print_string
+
+    ld      ra, 0(sp)
+    addi    sp, sp, 48
+    ret
+
+# Print the bool in a0 as true or false
+.global print_bool
+print_bool:
+    beqz    a0, 1f
+    la      a0, _TRUE
+    j       print_string
+1:
+    la      a0, _FALSE
+    j       print_string
+
+.global print_newline
+print_newline:
+    li      a0, 10              # '\n'
+    j       write_char
+# Print end
+
+# Conversion start
+# The to_string, parse_int and parse_bool builtins. Strings built at
+# runtime are allocated on the heap at tp, and never freed.
+
+# Write the signed integer in a0 in decimal backwards into the buffer
+# ending at a1, and return the start of the digits in a0
+.global format_int
+format_int:
+    mv      t2, a0
+    li      t1, 10
+    # The remainders are negative for negative numbers, so that the
+    # smallest integer doesn't overflow.
+1:
+    rem     t0, a0, t1
+    div     a0, a0, t1
+    bgez    t0, 2f
+    neg     t0, t0
+2:
+    addi    t0, t0, 48          # '0'
+    addi    a1, a1, -1
+    sb      t0, 0(a1)
+    bnez    a0, 1b
+
+    bgez    t2, 3f
+    li      t0, 45              # '-'
+    addi    a1, a1, -1
+    sb      t0, 0(a1)
+3:
+    mv      a0, a1
+    ret
+
+# Convert the signed integer in a0 to a decimal string on the heap
+.global int_to_string
+int_to_string:
+    addi    sp, sp, -48
+    sd      ra, 0(sp)
+
+    sb      zero, 47(sp)
+    addi    a1, sp, 47
+    call    format_int
+
+    # Copy the digits and the null terminator
+    mv      t0, tp
+1:
+    lbu     t1, 0(a0)
+    sb      t1, 0(t0)
+    addi    a0, a0, 1
+    addi    t0, t0, 1
+    bnez    t1, 1b
+
+    # Keep the heap aligned
+    mv      a0, tp
+    addi    t0, t0, 7
+    andi    tp, t0, -8
+
+    ld      ra, 0(sp)
+    addi    sp, sp, 48
+    ret
+
+# Convert the bool in a0 to true or false
+.global bool_to_string
+bool_to_string:
+    beqz    a0, 1f
+    la      a0, _TRUE
+    ret
+1:
+    la      a0, _FALSE
+    ret
+
+# Parse the string in a0 as a decimal integer with an optional sign
+.global parse_int
+parse_int:
+    mv      t5, a0
+    li      t1, 0               # Whether the integer is negative
+    lbu     t0, 0(a0)
+    li      t2, 45              # '-'
+    bne     t0, t2, 1f
+    li      t1, 1
+    addi    a0, a0, 1
+    j       2f
+1:
+    li      t2, 43              # '+'
+    bne     t0, t2, 2f
+    addi    a0, a0, 1
+2:
+    # There has to be at least one digit
+    lbu     t0, 0(a0)
+    beqz    t0, 6f
+
+    # Subtract the digits from zero, since the smallest integer has no
+    # positive counterpart
+    li      t2, 0
+    li      t3, 10
+3:
+    lbu     t0, 0(a0)
+    beqz    t0, 4f
+    addi    t0, t0, -48         # '0'
+    bgeu    t0, t3, 6f
+    mul     t4, t2, t3
+    mulh    t6, t2, t3
+    srai    a1, t4, 63
+    bne     t6, a1, 6f
+    sub     t2, t4, t0
+    bgt     t2, t4, 6f
+    addi    a0, a0, 1
+    j       3b
+4:
+    bnez    t1, 5f
+    neg     a0, t2
+    bltz    a0, 6f
+    ret
+5:
+    mv      a0, t2
+    ret
+6:
+    mv      a0, t5
+    la      a1, _PARSE_INT_MESSAGE
+    j       parse_error
+
+# Parse the string in a0 as true or false
+.global parse_bool
+parse_bool:
+    addi    sp, sp, -16
+    sd      ra, 0(sp)
+    sd      s0, 8(sp)
+
+    mv      s0, a0
+    la      a1, _TRUE
+    call    string_equal
+    bnez    a0, 1f
+
+    mv      a0, s0
+    la      a1, _FALSE
+    call    string_equal
+    beqz    a0, 2f
+    li      a0, 0
+1:
+    ld      ra, 0(sp)
+    ld      s0, 8(sp)
+    addi    sp, sp, 16
+    ret
+2:
+    mv      a0, s0
+    la      a1, _PARSE_BOOL_MESSAGE
+    j       parse_error
+
+# Return whether the null-terminated strings in a0 and a1 are equal
+.global string_equal
+string_equal:
+    lbu     t0, 0(a0)
+    lbu     t1, 0(a1)
+    bne     t0, t1, 1f
+    beqz    t0, 2f
+    addi    a0, a0, 1
+    addi    a1, a1, 1
+    j       string_equal
+1:
+    li      a0, 0
+    ret
+2:
+    li      a0, 1
+    ret
+
+# Report that the string in a0 could not be parsed, with the rest of the
+# message in a1. This doesn't return, so it doesn't preserve any registers.
+parse_error:
+    mv      s0, a0
+    mv      s1, a1
+    la      a0, _PARSE_ERROR_MESSAGE
+    call    print_string
+    mv      a0, s0
+    call    print_string
+    mv      a0, s1
+    j       runtime_error
+# Conversion end
+
 # Printf start
 .global printf
 printf:
@@ -1,16 +1,195 @@
+use crate::builtin::Builtin;
+use crate::compiler::helper::{ARGUMENT_REGISTERS, load_instruction, pop, push};
+use crate::compiler::label::Labels;
 use crate::compiler::stack::Stack;
-use crate::parser::ast::{ExpressionNode, TermNode};
+use crate::compiler::types::Types;
+use crate::parser::ast::{BinaryVerb, Expression, ExpressionNode, TermNode, Type, UnaryVerb};
 
-pub fn compile_expression(expression: &ExpressionNode, stack: &Stack) -> Result<String, String> {
-    match expression {
-        ExpressionNode::BinaryOperation { .. } => todo!(),
+/// Compile an expression, leaving the result in t0.
+pub fn compile_expression(expression: &Expression, stack: &Stack, types: &Types, labels: &mut Labels) -> Result<String, String> {
+    match &expression.node {
+        ExpressionNode::BinaryOperation { verb: verb @ (BinaryVerb::And | BinaryVerb::Or), lhs, rhs } => {
+            // Skip the right hand side if the left hand side decides the result
+            let end = labels.next("logic_end");
+            let branch = match verb {
+                BinaryVerb::And => "beqz",
+                _ => "bnez",
+            };
+
+            let lhs = compile_expression(lhs, stack, types, labels)?;
+            let rhs = compile_expression(rhs, stack, types, labels)?;
+
+            Ok(format!("\
+{lhs}    {branch}    t0, {end}
+{rhs}{end}:
+"))
+        },
+        ExpressionNode::BinaryOperation { verb, lhs, rhs } => {
+            if types.expression(lhs, stack)? == Some(Type::String) {
+                Err(format!("Operator {} on strings is not supported by the compiler", verb.symbol()))?
+            }
+
+            let mut code = compile_expression(lhs, stack, types, labels)?;
+            code.push_str(&push("t0"));
+            code.push_str(&compile_expression(rhs, stack, types, labels)?);
+            code.push_str("    mv      t1, t0\n");
+            code.push_str(&pop("t0"));
+
+            code.push_str(match verb {
+                // The result overflowed if adding a negative number didn't make it smaller
+                BinaryVerb::Plus => concat!(
+                    "    add     t2, t0, t1\n",
+                    "    slt     t3, t2, t0\n",
+                    "    slti    t4, t1, 0\n",
+                    "    beq     t3, t4, 1f\n",
+                    "    la      a0, _INTEGER_OVERFLOW_MESSAGE\n",
+                    "    j       runtime_error\n",
+                    "1:  mv      t0, t2\n",
+                ),
+                BinaryVerb::Minus => concat!(
+                    "    sub     t2, t0, t1\n",
+                    "    slt     t3, t2, t0\n",
+                    "    sgtz    t4, t1\n",
+                    "    beq     t3, t4, 1f\n",
+                    "    la      a0, _INTEGER_OVERFLOW_MESSAGE\n",
+                    "    j       runtime_error\n",
+                    "1:  mv      t0, t2\n",
+                ),
+                // The upper half of the product must only repeat the sign of the lower half
+                BinaryVerb::Multiply => concat!(
+                    "    mul     t2, t0, t1\n",
+                    "    mulh    t3, t0, t1\n",
+                    "    srai    t4, t2, 63\n",
+                    "    beq     t3, t4, 1f\n",
+                    "    la      a0, _INTEGER_OVERFLOW_MESSAGE\n",
+                    "    j       runtime_error\n",
+                    "1:  mv      t0, t2\n",
+                ),
+                BinaryVerb::Divide => concat!(
+                    "    call    check_division\n",
+                    "    div     t0, t0, t1\n",
+                ),
+                BinaryVerb::Modulo => concat!(
+                    "    call    check_division\n",
+                    "    rem     t0, t0, t1\n",
+                ),
+                BinaryVerb::WrappingPlus => "    add     t0, t0, t1\n",
+                BinaryVerb::WrappingMinus => "    sub     t0, t0, t1\n",
+                BinaryVerb::WrappingMultiply => "    mul     t0, t0, t1\n",
+                BinaryVerb::Compare => "    sub     t0, t0, t1\n    seqz    t0, t0\n",
+                BinaryVerb::NotEqual => "    sub     t0, t0, t1\n    snez    t0, t0\n",
+                BinaryVerb::Less => "    slt     t0, t0, t1\n",
+                BinaryVerb::LessEqual => "    slt     t0, t1, t0\n    xori    t0, t0, 1\n",
+                BinaryVerb::Greater => "    slt     t0, t1, t0\n",
+                BinaryVerb::GreaterEqual => "    slt     t0, t0, t1\n    xori    t0, t0, 1\n",
+                BinaryVerb::And | BinaryVerb::Or => unreachable!("Logical operators are short-circuited above"),
+            });
+
+            Ok(code)
+        },
+        ExpressionNode::Unary { op, operand } => {
+            let mut code = compile_expression(operand, stack, types, labels)?;
+
+            code.push_str(match op {
+                UnaryVerb::Plus => "",
+                // Only the smallest integer is negative after negating it
+                UnaryVerb::Minus => concat!(
+                    "    neg     t1, t0\n",
+                    "    and     t2, t0, t1\n",
+                    "    bgez    t2, 1f\n",
+                    "    la      a0, _INTEGER_OVERFLOW_MESSAGE\n",
+                    "    j       runtime_error\n",
+                    "1:  mv      t0, t1\n",
+                ),
+                UnaryVerb::Not => "    seqz    t0, t0\n",
+            });
+
+            Ok(code)
+        },
+        ExpressionNode::Call { name, args } => {
+            if let Some(builtin) = Builtin::from_name(name) {
+                return compile_builtin(builtin, args, stack, types, labels);
+            }
+
+            if args.len() > ARGUMENT_REGISTERS {
+                Err(format!("Function {name} is called with {} arguments, but at most {ARGUMENT_REGISTERS} are supported", args.len()))?
+            }
+
+            // Keep the evaluated arguments on the stack, since evaluating
+            // the next argument might involve another call.
+            let mut code = String::new();
+            for arg in args {
+                code.push_str(&compile_expression(arg, stack, types, labels)?);
+                code.push_str(&push("t0"));
+            }
+
+            for i in (0..args.len()).rev() {
+                code.push_str(&pop(&format!("a{i}")));
+            }
+
+            code.push_str(&format!("    call    {name}\n"));
+            code.push_str("    mv      t0, a0\n");
+
+            Ok(code)
+        },
         ExpressionNode::Term(term) => match term {
             TermNode::Integer(x) => {
                 Ok(format!("    li      t0, {x} # Load term {x}\n"))
             },
-            TermNode::Boolean(_) => todo!(),
-            TermNode::Variable(_) => todo!(),
-            TermNode::String(_) => todo!(),
+            TermNode::Boolean(x) => {
+                Ok(format!("    li      t0, {} # Load term {x}\n", *x as u8))
+            },
+            TermNode::Variable(name) => {
+                let (offset, type_name) = stack.get_variable(name)?;
+
+                Ok(format!("    {}      t0, {offset}(s0) # Load variable {name}\n", load_instruction(type_name)))
+            },
+            TermNode::String(text) => {
+                Ok(format!("    la      t0, {} # Load string\n", labels.string(text)))
+            },
+        },
+    }
+}
+
+/// Compile a call to a builtin, leaving the result in t0.
+fn compile_builtin(builtin: Builtin, args: &[Expression], stack: &Stack, types: &Types, labels: &mut Labels) -> Result<String, String> {
+    match (builtin, args) {
+        _ if args.len() != builtin.parameters().len() => {
+            Err(format!("Function {} expected {} arguments, got {}", builtin.name(), builtin.parameters().len(), args.len()))
+        },
+        (Builtin::Print | Builtin::Println, [value]) => {
+            let print = match types.expression(value, stack)? {
+                Some(Type::Int) => "print_int",
+                Some(Type::Bool) => "print_bool",
+                Some(Type::String) => "print_string",
+                None => Err(format!("The argument of {} has no value", builtin.name()))?,
+            };
+
+            let mut code = compile_expression(value, stack, types, labels)?;
+            code.push_str(&format!("    mv      a0, t0\n    call    {print}\n"));
+
+            if builtin == Builtin::Println {
+                code.push_str("    call    print_newline\n");
+            }
+
+            Ok(code)
+        },
+        (Builtin::ToString | Builtin::ParseInt | Builtin::ParseBool, [value]) => {
+            let routine = match (builtin, types.expression(value, stack)?) {
+                (_, None) => Err(format!("The argument of {} has no value", builtin.name()))?,
+                // Strings are immutable, so the same string can be used
+                (Builtin::ToString, Some(Type::String)) => return compile_expression(value, stack, types, labels),
+                (Builtin::ToString, Some(Type::Int)) => "int_to_string",
+                (Builtin::ToString, Some(Type::Bool)) => "bool_to_string",
+                (Builtin::ParseInt, _) => "parse_int",
+                _ => "parse_bool",
+            };
+
+            let mut code = compile_expression(value, stack, types, labels)?;
+            code.push_str(&format!("    mv      a0, t0\n    call    {routine}\n    mv      t0, a0\n"));
+
+            Ok(code)
         },
+        _ => Err(format!("Function {} is not supported by the compiler", builtin.name())),
     }
-}
\ No newline at end of file
+}
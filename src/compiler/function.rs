@@ -1,46 +1,81 @@
 use crate::compiler::block::compile_block;
-use crate::compiler::helper::store_instruction;
+use crate::compiler::helper::{ARGUMENT_REGISTERS, store_instruction};
+use crate::compiler::label::Labels;
 use crate::compiler::stack::Stack;
-use crate::parser::ast::{BlockNode, FunctionNode, Type};
+use crate::compiler::types::Types;
+use crate::parser::ast::{BlockNode, FunctionNode, Statement, Variable};
 
-pub fn compile_function(function: &FunctionNode) -> Result<String, String> {
+pub fn compile_function(function: &FunctionNode, types: &Types) -> Result<String, String> {
     let mut stack = Stack::new();
 
-    let return_address_name = "__return_address__";
-    stack.add_variable(return_address_name, Type::Int);
-
-    for param in &function.parameters {
-        stack.add_variable(&param.name, param.type_name)
+    if function.parameters.len() > ARGUMENT_REGISTERS {
+        Err(format!("Function {} has {} parameters, but at most {ARGUMENT_REGISTERS} are supported", function.name, function.parameters.len()))?
     }
 
-    for node in &function.block {
-        if let BlockNode::VariableDefinition { name, type_name, .. } = node {
-            stack.add_variable(name, *type_name);
-        }
+    // The parameters use the first slots
+    for (slot, param) in function.parameters.iter().enumerate() {
+        stack.add_variable(slot, param.type_name)
     }
 
+    add_variables(&function.block, &mut stack);
+
+    let ra = Stack::RETURN_ADDRESS;
+    let fp = Stack::FRAME_POINTER;
+
+    // The frame pointer s0 points to the bottom of the frame, so that
+    // temporaries can be pushed to the stack without moving the variables.
     let mut preamble = format!("\
 {name}: # Preamble
     addi    sp, sp, -{size}
-    sd      ra, -{ra}(sp)
-", name=function.name, size=stack.size(), ra=stack.offset(return_address_name).ok_or("Can not find return address offset")?);
+    sd      ra, {ra}(sp)
+    sd      s0, {fp}(sp)
+    mv      s0, sp
+", name=function.name, size=stack.size());
 
     for (i, param) in function.parameters.iter().enumerate() {
-        let offset = stack.offset(&param.name).ok_or(format!("Could not get offset for parameter {}", param.name))?;
+        let offset = stack.offset(i);
 
-        preamble.push_str(&format!("\
-    {}      a{}, -{}(sp)
-", store_instruction(param.type_name), i, offset))
+        preamble.push_str(&format!("    {}      a{}, {}(s0)\n", store_instruction(param.type_name), i, offset))
     }
 
     preamble.push_str("    # Preamble end");
 
     let postamble = format!("\
 0:  # Postamble
-    ld      ra, -{ra}(sp)
+    mv      sp, s0
+    ld      ra, {ra}(sp)
+    ld      s0, {fp}(sp)
     addi    sp, sp, {size}
     ret
-", size=stack.size(), ra=stack.offset(return_address_name).ok_or("Can not find return address offset")?);
+", size=stack.size());
+
+    let mut labels = Labels::new(&function.name);
 
-    Ok(format!("{preamble}\n\n{}\n{postamble}", compile_block(&function.block, &stack)?))
-}
\ No newline at end of file
+    let body = compile_block(&function.block, &stack, types, &mut labels)?;
+
+    Ok(format!("{preamble}\n\n{body}\n{postamble}{}", labels.data()))
+}
+
+/// Reserve space in the frame for the variables defined in the block,
+/// including those in nested blocks.
+fn add_variables(block: &[Statement], stack: &mut Stack) {
+    for node in block {
+        match &node.node {
+            BlockNode::VariableDefinition { name: Variable { slot: Some(slot), .. }, type_name, .. } => {
+                stack.add_variable(*slot, *type_name);
+            }
+            BlockNode::IfStatement { block, else_block, .. } => {
+                add_variables(block, stack);
+
+                if let Some(else_block) = else_block {
+                    add_variables(else_block, stack);
+                }
+            }
+            BlockNode::Block(nested)
+            | BlockNode::WhileLoop { block: nested, .. } => {
+                add_variables(nested, stack);
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,10 +1,17 @@
 use crate::parser::ast::Type;
 
+/// The number of arguments that can be passed in registers a0-a7.
+pub const ARGUMENT_REGISTERS: usize = 8;
+
+/// The size of a type on the stack.
+///
+/// Every value uses a full register-width slot, so that all slots are aligned.
+/// Strings are pointers to null-terminated strings.
 pub fn type_size(type_name: Type) -> usize {
     match type_name {
         Type::Int => 8,
-        Type::Bool => 1,
-        Type::String => todo!(),
+        Type::Bool => 8,
+        Type::String => 8,
     }
 }
 
@@ -16,3 +23,22 @@ pub fn store_instruction(type_name: Type) -> &'static str {
         x => panic!("Invalid type size {x}")
     }
 }
+
+pub fn load_instruction(type_name: Type) -> &'static str {
+    match type_size(type_name) {
+        1 => "lb",
+        4 => "lw",
+        8 => "ld",
+        x => panic!("Invalid type size {x}")
+    }
+}
+
+/// Push a register onto the stack of temporaries.
+pub fn push(register: &str) -> String {
+    format!("    addi    sp, sp, -8\n    sd      {register}, 0(sp)\n")
+}
+
+/// Pop the top of the stack of temporaries into a register.
+pub fn pop(register: &str) -> String {
+    format!("    ld      {register}, 0(sp)\n    addi    sp, sp, 8\n")
+}
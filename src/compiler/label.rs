@@ -0,0 +1,78 @@
+/// Generates unique labels within a function, and keeps track of the
+/// loops that `break` and `continue` jump out of, and of the string
+/// literals that are stored as data.
+pub struct Labels {
+    function: String,
+    count: usize,
+    loops: Vec<Loop>,
+    /// The label and text of each string literal.
+    strings: Vec<(String, String)>,
+}
+
+pub struct Loop {
+    pub start: String,
+    pub end: String,
+}
+
+impl Labels {
+    pub fn new(function: &str) -> Self {
+        Labels {
+            function: function.into(),
+            count: 0,
+            loops: vec![],
+            strings: vec![],
+        }
+    }
+
+    /// Create a new label, unique within the program.
+    pub fn next(&mut self, kind: &str) -> String {
+        self.count += 1;
+        format!(".L{}_{}_{}", self.function, kind, self.count)
+    }
+
+    pub fn loop_scope<T, F>(&mut self, start: &str, end: &str, f: F) -> T
+        where F: FnOnce(&mut Labels) -> T
+    {
+        self.loops.push(Loop { start: start.into(), end: end.into() });
+        let result = f(self);
+        self.loops.pop();
+        result
+    }
+
+    /// Store a string literal as data, and return its label.
+    pub fn string(&mut self, text: &str) -> String {
+        let label = self.next("string");
+        self.strings.push((label.clone(), text.to_string()));
+        label
+    }
+
+    /// The read-only data for the string literals, as null-terminated strings.
+    pub fn data(&self) -> String {
+        if self.strings.is_empty() {
+            return String::new();
+        }
+
+        let mut data = String::from("    .pushsection .rodata\n");
+
+        for (label, text) in &self.strings {
+            let escaped: String = text.chars().map(|c| match c {
+                '"' => "\\\"".to_string(),
+                '\\' => "\\\\".to_string(),
+                '\n' => "\\n".to_string(),
+                '\t' => "\\t".to_string(),
+                c if c.is_ascii_control() => format!("\\{:03o}", c as u8),
+                c => c.to_string(),
+            }).collect();
+
+            data.push_str(&format!("{label}:\n    .string \"{escaped}\"\n"));
+        }
+
+        data.push_str("    .popsection\n");
+        data
+    }
+
+    /// The innermost loop, if any.
+    pub fn current_loop(&self) -> Option<&Loop> {
+        self.loops.last()
+    }
+}
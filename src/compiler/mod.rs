@@ -3,16 +3,20 @@ mod stack;
 mod helper;
 mod block;
 mod expression;
+mod label;
+mod types;
 
 use crate::compiler::function::compile_function;
+use crate::compiler::types::Types;
 use crate::parser::ast::Program;
 
 pub fn compile(program: &Program) -> Result<String, String> {
     let preamble = include_str!("boot.s");
+    let types = Types::new(program);
 
     let functions = program.nodes
         .iter()
-        .map(compile_function)
+        .map(|function| compile_function(function, &types))
         .collect::<Result<Vec<_>, _>>()?
         .join("\n");
 
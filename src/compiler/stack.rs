@@ -1,57 +1,54 @@
 use crate::compiler::helper::type_size;
-use crate::parser::ast::Type;
-
-pub struct StackEntry {
-    name: String,
-    type_name: Type,
+use crate::parser::ast::{Type, Variable};
+
+/// The layout of the frame of a function, relative to the frame pointer.
+///
+/// The return address and the frame pointer of the caller are at the bottom,
+/// followed by the variables in the order of their slots.
+pub struct Stack {
+    /// The types of the variables, indexed by the slots given by the resolver.
+    slots: Vec<Option<Type>>,
 }
 
-pub struct Stack<'a> {
-    parent: Option<&'a Stack<'a>>,
-    variables: Vec<StackEntry>,
-}
+impl Stack {
+    pub const RETURN_ADDRESS: usize = 0;
+    pub const FRAME_POINTER: usize = 8;
+    const VARIABLES: usize = 16;
 
-impl<'a> Stack<'a> {
     pub fn new() -> Self {
         Stack {
-            parent: None,
-            variables: vec![],
+            slots: vec![],
         }
     }
 
-    pub fn add_variable(&mut self, name: &str, type_name: Type) {
-        self.variables.push(
-            StackEntry {
-                name: name.into(),
-                type_name: type_name.clone(),
-            }
-        )
+    pub fn add_variable(&mut self, slot: usize, type_name: Type) {
+        if self.slots.len() <= slot {
+            self.slots.resize(slot + 1, None);
+        }
+
+        self.slots[slot] = Some(type_name);
     }
 
-    pub fn get_variable(&self, name: &str) -> Option<(usize, Type)> {
-        let mut offset = 0;
-
-        self.variables.iter().rev().find_map(|var| {
-            if var.name == name {
-                Some((offset, var.type_name.clone()))
-            } else {
-                offset += type_size(var.type_name);
-                None
-            }
-        }).or_else(||
-            self.parent.and_then(|parent|
-                parent.get_variable(name)
-            ).map(|(parent_offset, type_name)|
-                (offset + parent_offset, type_name)
-            )
-        )
+    /// The offset of a slot from the frame pointer.
+    pub fn offset(&self, slot: usize) -> usize {
+        Self::VARIABLES + self.slots[..slot]
+            .iter()
+            .flatten()
+            .map(|type_name| type_size(*type_name))
+            .sum::<usize>()
     }
 
-    pub fn offset(&self, name: &str) -> Option<usize> {
-        self.get_variable(&name).map(|x| x.0)
+    /// The offset and type of a resolved variable.
+    pub fn get_variable(&self, variable: &Variable) -> Result<(usize, Type), String> {
+        let slot = variable.slot.ok_or(format!("Variable {variable} has not been resolved"))?;
+
+        match self.slots.get(slot) {
+            Some(Some(type_name)) => Ok((self.offset(slot), *type_name)),
+            _ => Err(format!("Could not get offset for variable {variable}")),
+        }
     }
 
     pub fn size(&self) -> usize {
-        self.variables.iter().fold(0, |acc, v| acc + type_size(v.type_name))
+        self.offset(self.slots.len())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+use crate::builtin::Builtin;
+use crate::compiler::stack::Stack;
+use crate::parser::ast::{BinaryVerb, Expression, ExpressionNode, Program, TermNode, Type, UnaryVerb};
+
+/// Finds the types of expressions in a type checked program, for the
+/// operations that depend on the type of their operands.
+pub struct Types<'a> {
+    /// The return type of each function.
+    functions: HashMap<&'a str, Option<Type>>,
+}
+
+impl<'a> Types<'a> {
+    pub fn new(program: &'a Program) -> Self {
+        Types {
+            functions: program.nodes
+                .iter()
+                .map(|function| (function.name.as_str(), function.return_type))
+                .collect(),
+        }
+    }
+
+    /// The type of the expression, or `None` for a call to a function without a return value.
+    pub fn expression(&self, expression: &Expression, stack: &Stack) -> Result<Option<Type>, String> {
+        let type_name = match &expression.node {
+            ExpressionNode::BinaryOperation { verb, lhs, .. } => match verb {
+                BinaryVerb::Plus | BinaryVerb::Minus | BinaryVerb::Multiply | BinaryVerb::Divide | BinaryVerb::Modulo
+                | BinaryVerb::WrappingPlus | BinaryVerb::WrappingMinus | BinaryVerb::WrappingMultiply => {
+                    return self.expression(lhs, stack);
+                }
+                _ => Type::Bool,
+            },
+            ExpressionNode::Unary { op: UnaryVerb::Not, .. } => Type::Bool,
+            ExpressionNode::Unary { .. } => Type::Int,
+            ExpressionNode::Call { name, .. } => {
+                return match (self.functions.get(name.as_str()), Builtin::from_name(name)) {
+                    (Some(return_type), _) => Ok(*return_type),
+                    (None, Some(builtin)) => Ok(builtin.return_type()),
+                    (None, None) => Err(format!("Undefined function {name}")),
+                };
+            }
+            ExpressionNode::Term(term) => match term {
+                TermNode::Variable(variable) => stack.get_variable(variable)?.1,
+                TermNode::Boolean(_) => Type::Bool,
+                TermNode::Integer(_) => Type::Int,
+                TermNode::String(_) => Type::String,
+            },
+        };
+
+        Ok(Some(type_name))
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt::{Display, Formatter};
+use crate::{flow, lint, resolver, typeck};
+use crate::parser::ast::{Program, Signature};
+use crate::resolver::ResolveError;
+use crate::typeck::TypeError;
+
+/// How serious a diagnostic from the static analysis is.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum Severity {
+    /// Reported, but the program can still run.
+    Warning,
+    /// The program is not run or compiled.
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A diagnostic from any of the static analysis passes.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Diagnostic {
+    Resolve(ResolveError),
+    Type(TypeError),
+    Flow(flow::Diagnostic),
+    Lint(lint::Diagnostic),
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::Resolve(_) | Self::Type(_) => Severity::Error,
+            Self::Flow(diagnostic) => diagnostic.severity(),
+            Self::Lint(diagnostic) => diagnostic.severity,
+        }
+    }
+
+    /// Format the diagnostic with the location prefixed by the file name.
+    pub fn report(&self, file: &str) -> String {
+        match self {
+            Self::Resolve(error) => error.report(file),
+            Self::Type(error) => error.report(file),
+            Self::Flow(diagnostic) => diagnostic.report(file),
+            Self::Lint(diagnostic) => diagnostic.report(file),
+        }
+    }
+}
+
+/// Run the static analysis of a program that can call the given native
+/// functions, and return the errors and warnings.
+///
+/// The other passes rely on the names being resolved, so they only run
+/// if the resolver didn't find any errors. The lints are only run if a
+/// config for them is given.
+pub fn analyze(program: &mut Program, natives: &[Signature], lints: Option<&lint::Config>) -> Vec<Diagnostic> {
+    if let Err(errors) = resolver::resolve_with_natives(program, natives) {
+        return errors.into_iter().map(Diagnostic::Resolve).collect();
+    }
+
+    let type_errors = typeck::check_with_natives(program, natives).err().unwrap_or_default();
+    let flow_diagnostics = flow::analyze(program);
+    let lint_diagnostics = lints.map(|config| lint::lint(program, config)).unwrap_or_default();
+
+    type_errors.into_iter().map(Diagnostic::Type)
+        .chain(flow_diagnostics.into_iter().map(Diagnostic::Flow))
+        .chain(lint_diagnostics.into_iter().map(Diagnostic::Lint))
+        .collect()
+}
@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod test;
+
+use std::fmt::{Display, Formatter};
+use crate::diagnostic::Severity;
+use crate::parser::ast::{BlockNode, ExpressionNode, FunctionNode, Ident, Program, Span, Statement, TermNode};
+
+/// A problem with the control flow of a function.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Kind {
+    /// A function with a return type that can finish without returning.
+    MissingReturn(Ident),
+    BreakOutsideLoop,
+    ContinueOutsideLoop,
+    /// A statement that can never run.
+    Unreachable,
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingReturn(name) => write!(f, "Function {name} does not return a value on every path"),
+            Self::BreakOutsideLoop => write!(f, "Break outside of a loop"),
+            Self::ContinueOutsideLoop => write!(f, "Continue outside of a loop"),
+            Self::Unreachable => write!(f, "Unreachable statement"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub kind: Kind,
+    pub span: Span,
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        match self.kind {
+            Kind::Unreachable => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    /// Format the diagnostic with the location prefixed by the file name.
+    pub fn report(&self, file: &str) -> String {
+        format!("{file}:{}: {}: {}", self.span, self.severity(), self.kind)
+    }
+}
+
+/// Check that functions with a return type return on every path, and
+/// find statements that can never run.
+pub fn analyze(program: &Program) -> Vec<Diagnostic> {
+    let mut analyzer = Analyzer {
+        loops: vec![],
+        diagnostics: vec![],
+    };
+
+    for function in &program.nodes {
+        analyzer.analyze_function(function);
+    }
+
+    analyzer.diagnostics
+}
+
+/// How running a statement can end.
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Exit {
+    /// It can continue with the next statement.
+    Next,
+    /// It always leaves the innermost loop, with `break` or `continue`.
+    Loop,
+    /// It always returns from the function, or never ends.
+    Function,
+}
+
+struct Analyzer {
+    /// Whether each loop around the current statement has a `break`, innermost last.
+    loops: Vec<bool>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Analyzer {
+    fn report(&mut self, kind: Kind, span: Span) {
+        self.diagnostics.push(Diagnostic { kind, span });
+    }
+
+    fn analyze_function(&mut self, function: &FunctionNode) {
+        let exit = self.analyze_block(&function.block);
+
+        if function.return_type.is_some() && exit == Exit::Next {
+            self.report(Kind::MissingReturn(function.name.clone()), function.span);
+        }
+    }
+
+    fn analyze_block(&mut self, block: &[Statement]) -> Exit {
+        let mut exit = Exit::Next;
+        let mut reported = false;
+
+        for node in block {
+            // Only the first unreachable statement is reported, but the rest
+            // are still analyzed to find errors in them
+            if exit != Exit::Next && !reported {
+                self.report(Kind::Unreachable, node.span);
+                reported = true;
+            }
+
+            let node_exit = self.analyze_statement(node);
+
+            if exit == Exit::Next {
+                exit = node_exit;
+            }
+        }
+
+        exit
+    }
+
+    fn analyze_statement(&mut self, node: &Statement) -> Exit {
+        match &node.node {
+            BlockNode::Return(_) => Exit::Function,
+            BlockNode::Break => {
+                match self.loops.last_mut() {
+                    Some(has_break) => *has_break = true,
+                    None => self.report(Kind::BreakOutsideLoop, node.span),
+                }
+
+                Exit::Loop
+            }
+            BlockNode::Continue => {
+                if self.loops.is_empty() {
+                    self.report(Kind::ContinueOutsideLoop, node.span);
+                }
+
+                Exit::Loop
+            }
+            BlockNode::Block(nested) => self.analyze_block(nested),
+            BlockNode::IfStatement { block, else_block, .. } => {
+                let then_exit = self.analyze_block(block);
+                let else_exit = match else_block {
+                    Some(else_block) => self.analyze_block(else_block),
+                    None => Exit::Next,
+                };
+
+                match (then_exit, else_exit) {
+                    (Exit::Next, _) | (_, Exit::Next) => Exit::Next,
+                    (Exit::Function, Exit::Function) => Exit::Function,
+                    _ => Exit::Loop,
+                }
+            }
+            BlockNode::WhileLoop { condition, block } => {
+                self.loops.push(false);
+                self.analyze_block(block);
+                let has_break = self.loops.pop().expect("Loop stack is empty");
+
+                // A `while (true)` loop without a `break` never ends
+                let infinite = matches!(condition.node, ExpressionNode::Term(TermNode::Boolean(true)));
+
+                if infinite && !has_break {
+                    Exit::Function
+                } else {
+                    Exit::Next
+                }
+            }
+            BlockNode::VariableDefinition { .. }
+            | BlockNode::Assignment { .. }
+            | BlockNode::Expression(_) => Exit::Next,
+        }
+    }
+}
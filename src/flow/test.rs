@@ -0,0 +1,139 @@
+use crate::diagnostic::Severity;
+use crate::parser::parse;
+use super::{analyze, Kind};
+
+/// Parse and analyze a program, and return the kinds and lines of the diagnostics.
+fn diagnostics(source: &str) -> Vec<(Kind, usize)> {
+    let program = parse(source).expect("Could not parse program");
+
+    analyze(&program)
+        .into_iter()
+        .map(|diagnostic| (diagnostic.kind, diagnostic.span.line))
+        .collect()
+}
+
+#[test]
+fn test_example_programs() {
+    for source in [
+        include_str!("../../tests/arithmetic.kw"),
+        include_str!("../../tests/expression.kw"),
+        include_str!("../../tests/function_call.kw"),
+        include_str!("../../tests/if_else.kw"),
+        include_str!("../../tests/if_statement.kw"),
+        include_str!("../../tests/logic.kw"),
+        include_str!("../../tests/simple_return.kw"),
+        include_str!("../../tests/variable_definition.kw"),
+        include_str!("../../tests/while_loop.kw"),
+    ] {
+        assert_eq!(diagnostics(source), vec![]);
+    }
+}
+
+#[test]
+fn test_missing_return() {
+    let diagnostics = diagnostics("
+        function if_without_else(x: int) -> int {
+            if (x > 0) {
+                return 1;
+            }
+        }
+
+        function if_else(x: int) -> int {
+            if (x > 0) {
+                return 1;
+            } else if (x < 0) {
+                return -1;
+            } else {
+                return 0;
+            }
+        }
+
+        function while_loop(x: int) -> int {
+            while (x > 0) {
+                return 1;
+            }
+        }
+
+        function infinite_loop() -> int {
+            while (true) {
+                if (false) {
+                    continue;
+                }
+            }
+        }
+
+        function infinite_loop_with_break() -> int {
+            while (true) {
+                break;
+            }
+        }
+
+        function no_return_type() {
+        }
+    ");
+
+    assert_eq!(
+        diagnostics,
+        vec![
+            (Kind::MissingReturn("if_without_else".into()), 2),
+            (Kind::MissingReturn("while_loop".into()), 18),
+            (Kind::MissingReturn("infinite_loop_with_break".into()), 32),
+        ],
+    );
+}
+
+#[test]
+fn test_unreachable() {
+    let diagnostics = diagnostics("
+        function main() -> int {
+            var x: int = 0;
+
+            while (x < 10) {
+                if (x == 5) {
+                    break;
+                    x = 6;
+                } else {
+                    continue;
+                }
+
+                x = x + 1;
+            }
+
+            return x;
+            x = 1;
+            x = 2;
+        }
+    ");
+
+    assert_eq!(
+        diagnostics,
+        vec![
+            (Kind::Unreachable, 8),
+            (Kind::Unreachable, 13),
+            (Kind::Unreachable, 17),
+        ],
+    );
+}
+
+#[test]
+fn test_jump_outside_loop() {
+    let program = parse("
+        function main() -> int {
+            if (true) {
+                break;
+            }
+
+            continue;
+        }
+    ").expect("Could not parse program");
+
+    let diagnostics = analyze(&program);
+
+    assert_eq!(
+        diagnostics.iter().map(|d| (d.kind.clone(), d.severity())).collect::<Vec<_>>(),
+        vec![
+            (Kind::BreakOutsideLoop, Severity::Error),
+            (Kind::ContinueOutsideLoop, Severity::Error),
+        ],
+    );
+}
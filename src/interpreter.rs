@@ -0,0 +1,570 @@
+use std::fmt::{Display, Formatter};
+use std::io::{self, Write};
+use crate::builtin::Builtin;
+use crate::diagnostic::{analyze, Diagnostic, Severity};
+use crate::lint;
+use crate::parser::ast::{Program, Span, Type};
+use crate::parser::{check_nesting, parse};
+use crate::stack::{self, PASS_STACK_PER_LEVEL};
+use crate::vm::{self, Limits};
+use crate::{bytecode, compiler, strings};
+use crate::vm::error::{ErrorKind, RuntimeError};
+use crate::vm::function::Functions;
+use crate::vm::native::NativeFunction;
+use crate::vm::value::Value;
+
+/// Runs KWLang functions from Rust.
+///
+/// Sources are loaded one at a time, and their functions can call the
+/// functions of every source loaded before. A source is only added if it
+/// parses and passes the static analysis.
+///
+/// The host application can add its own functions with [`Interpreter::register`].
+///
+/// ```
+/// use interpreter::{Interpreter, Value};
+///
+/// let mut interpreter = Interpreter::new();
+/// interpreter.load("function double(x: int) -> int { return 2 * x; }").unwrap();
+///
+/// assert_eq!(interpreter.call("double", vec![Value::Int(21)]), Ok(Some(Value::Int(42))));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Interpreter {
+    program: Program,
+    natives: Vec<NativeFunction>,
+    /// The functions of the program and the natives, for calling them.
+    functions: Functions,
+    limits: Limits,
+    /// The config of the lints, if they should run when loading a source.
+    lints: Option<lint::Config>,
+    /// How deeply the loaded sources are nested, see [`check_nesting`].
+    depth: usize,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::with_limits(Limits::default())
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An interpreter that stops calls exceeding the given limits.
+    pub fn with_limits(limits: Limits) -> Self {
+        let program = Program::default();
+        // The string functions of the language are natives as well
+        let natives = strings::natives();
+
+        Interpreter {
+            functions: Functions::new(&program, &natives, &limits),
+            program,
+            natives,
+            limits,
+            lints: None,
+            depth: 0,
+        }
+    }
+
+    /// Run the lints with the config when loading sources. Their warnings
+    /// are returned by [`Interpreter::load`], and lints set to errors stop
+    /// a source from loading.
+    ///
+    /// Unused functions are only reported when the config has entry
+    /// points, like `main` or the functions the host calls.
+    pub fn set_lints(&mut self, lints: lint::Config) {
+        self.lints = Some(lints);
+    }
+
+    /// Add a function implemented in Rust, which KWLang code can call.
+    ///
+    /// The function is only called with arguments of the given parameter
+    /// types, and must return a value of the return type. It has to be
+    /// registered before loading the sources that call it.
+    ///
+    /// Fails if a function with the same name is already registered or
+    /// loaded, or is a builtin.
+    pub fn register<F>(&mut self, name: &str, parameters: Vec<Type>, return_type: Option<Type>, function: F) -> Result<(), LoadError>
+        where F: Fn(&[Value]) -> Result<Option<Value>, ErrorKind> + Send + Sync + 'static
+    {
+        let defined = Builtin::from_name(name).is_some()
+            || self.natives.iter().any(|native| native.name() == name)
+            || self.program.nodes.iter().any(|function| function.name == name);
+
+        if defined {
+            return Err(LoadError::Redefined(name.into()));
+        }
+
+        self.natives.push(NativeFunction::new(name, parameters, return_type, function));
+        self.functions = Functions::new(&self.program, &self.natives, &self.limits);
+
+        Ok(())
+    }
+
+    /// Parse and check a source, and add its functions.
+    ///
+    /// Returns the warnings of the static analysis. If there are any errors
+    /// the source is not added, and the interpreter is left unchanged.
+    ///
+    /// The source may not be nested deeper than the maximum nesting depth
+    /// of the limits. The parser and the analysis run on their own thread,
+    /// with a stack large enough for the nesting of the sources.
+    pub fn load(&mut self, source: &str) -> Result<Vec<Diagnostic>, LoadError> {
+        let max = self.limits.max_nesting_depth;
+        let depth = check_nesting(source, max).map_err(|span| LoadError::Nesting { max, span })?;
+        // The analysis runs on the functions of the earlier sources as well
+        let depth = depth.max(self.depth);
+
+        let mut program = self.program.clone();
+        let signatures: Vec<_> = self.natives.iter().map(|native| native.signature.clone()).collect();
+        let lints = self.lints.as_ref();
+
+        let (program, diagnostics) = stack::run_nested(depth, PASS_STACK_PER_LEVEL, move || {
+            let loaded = parse(source).map_err(LoadError::Parse)?;
+            program.nodes.extend(loaded.nodes);
+
+            let diagnostics = analyze(&mut program, &signatures, lints);
+            Ok((program, diagnostics))
+        }).map_err(|error| LoadError::Thread(error.to_string()))??;
+
+        if diagnostics.iter().any(|diagnostic| diagnostic.severity() == Severity::Error) {
+            return Err(LoadError::Invalid(diagnostics));
+        }
+
+        self.functions = Functions::new(&program, &self.natives, &self.limits);
+        self.program = program;
+        self.depth = depth;
+
+        Ok(diagnostics)
+    }
+
+    /// All functions loaded so far, with the variables resolved.
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Call a loaded function, with `print` and `println` writing to stdout.
+    ///
+    /// Returns `None` if the function doesn't have a return value.
+    pub fn call(&self, name: &str, args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+        self.call_with_output(name, args, &mut io::stdout())
+    }
+
+    /// Call a loaded function, with `print` and `println` writing to the output.
+    pub fn call_with_output(&self, name: &str, args: Vec<Value>, output: &mut (dyn Write + Send)) -> Result<Option<Value>, RuntimeError> {
+        vm::call(&self.functions, name, args, self.limits.clone(), output)
+    }
+
+    /// Run the `main` function, and return its exit code.
+    pub fn run(&self) -> Result<i64, RuntimeError> {
+        Ok(vm::return_code(self.call("main", vec![])?)?)
+    }
+
+    /// Like [`Interpreter::run`], but compile the functions to bytecode and
+    /// run them on a stack machine instead of walking the syntax tree.
+    pub fn run_bytecode(&self) -> Result<i64, RuntimeError> {
+        self.run_bytecode_with_output(&mut io::stdout())
+    }
+
+    /// Like [`Interpreter::run_bytecode`], but with `print` and `println`
+    /// writing to the output.
+    pub fn run_bytecode_with_output(&self, output: &mut dyn Write) -> Result<i64, RuntimeError> {
+        // Only the compiler nests on the stack, the machine doesn't
+        let module = stack::run_nested(self.depth, PASS_STACK_PER_LEVEL, || {
+            bytecode::compile_with_natives(&self.program, &self.natives)
+        }).map_err(|error| ErrorKind::Thread(error.to_string()))?.map_err(ErrorKind::Bytecode)?;
+
+        bytecode::execute_with_output(&module, self.limits.clone(), output)
+    }
+
+    /// Compile the functions to RISC-V assembly, which runs the `main`
+    /// function. Native functions can't be called from the assembly.
+    pub fn compile_riscv(&self) -> Result<String, String> {
+        stack::run_nested(self.depth, PASS_STACK_PER_LEVEL, || compiler::compile(&self.program))
+            .map_err(|error| format!("Could not start the compiler: {error}"))?
+    }
+}
+
+/// Why a source couldn't be loaded, or a function couldn't be registered.
+#[derive(Debug, PartialEq, Clone)]
+pub enum LoadError {
+    /// The source isn't valid syntax.
+    Parse(String),
+    /// The static analysis found errors. Warnings are included as well.
+    Invalid(Vec<Diagnostic>),
+    /// The source is nested deeper than the limits allow, at the location.
+    Nesting { max: usize, span: Span },
+    /// The thread parsing and checking the source couldn't be started, for
+    /// example because there isn't enough memory for its stack.
+    Thread(String),
+    /// A function with the name of the registered function is already defined.
+    Redefined(String),
+}
+
+impl LoadError {
+    /// Format the error with the locations prefixed by the file name.
+    pub fn report(&self, file: &str) -> String {
+        match self {
+            Self::Parse(error) => format!("{file}: {error}"),
+            Self::Nesting { max, span } => format!("{file}:{span}: error: Exceeded the maximum nesting depth of {max}"),
+            Self::Thread(error) => format!("{file}: Could not start the parser: {error}"),
+            Self::Redefined(name) => format!("{file}: error: Function {name} is already defined"),
+            Self::Invalid(diagnostics) => diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.report(file))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.report("<source>"))
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+#[cfg(test)]
+mod test {
+    use crate::diagnostic::Severity;
+    use crate::lint::{Config, Lint};
+    use crate::parser::ast::Type;
+    use crate::parser::check_nesting;
+    use crate::vm::error::ErrorKind;
+    use crate::vm::value::Value;
+    use crate::vm::Limits;
+    use super::{Interpreter, LoadError};
+
+    #[test]
+    fn test_call() {
+        let mut interpreter = Interpreter::new();
+
+        interpreter.load("
+            function greet(name: string, excited: bool) -> string {
+                if (excited) {
+                    return \"Hello \" + name + \"!\";
+                }
+                return \"Hello \" + name;
+            }
+
+            function nothing() {}
+        ").expect("Could not load source");
+
+        assert_eq!(
+            interpreter.call("greet", vec![Value::String("KWLang".into()), Value::Bool(true)]),
+            Ok(Some(Value::String("Hello KWLang!".into()))),
+        );
+        assert_eq!(interpreter.call("nothing", vec![]), Ok(None));
+
+        let error = interpreter.call("greet", vec![Value::Int(1), Value::Bool(true)])
+            .expect_err("The argument has the wrong type");
+        assert!(matches!(error.kind, ErrorKind::TypeMismatch { .. }));
+
+        let error = interpreter.call("greet", vec![]).expect_err("The arguments are missing");
+        assert!(matches!(error.kind, ErrorKind::Arity { .. }));
+
+        let error = interpreter.call("missing", vec![]).expect_err("The function doesn't exist");
+        assert_eq!(error.kind, ErrorKind::UndefinedFunction("missing".into()));
+    }
+
+    #[test]
+    fn test_load() {
+        let mut interpreter = Interpreter::new();
+
+        interpreter.load("function one() -> int { return 1; }").expect("Could not load source");
+        interpreter.load("function two() -> int { return one() + one(); }").expect("Sources should see earlier sources");
+
+        assert_eq!(interpreter.call("two", vec![]), Ok(Some(Value::Int(2))));
+
+        let error = interpreter.load("function one() -> int { return true; }")
+            .expect_err("The source redefines a function and has a type error");
+        let LoadError::Invalid(diagnostics) = &error else {
+            panic!("Expected analysis errors, got {error:?}");
+        };
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+
+        interpreter.load("function three( -> int {}").expect_err("The source has a syntax error");
+
+        // Sources that failed to load are not added
+        assert_eq!(interpreter.program().nodes.len(), 2);
+        assert_eq!(interpreter.call("one", vec![]), Ok(Some(Value::Int(1))));
+
+        let warnings = interpreter.load("function four() -> int { return 4; return 5; }")
+            .expect("Warnings don't stop a source from loading");
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_native() {
+        let mut interpreter = Interpreter::new();
+
+        interpreter.register("add", vec![Type::Int, Type::Int], Some(Type::Int), |args| {
+            match args {
+                [Value::Int(x), Value::Int(y)] => Ok(Some(Value::Int(x + y))),
+                _ => unreachable!("The arguments are checked"),
+            }
+        }).expect("Could not register function");
+        interpreter.register("fail", vec![], None, |_| Err(ErrorKind::Native("Out of coffee".into())))
+            .expect("Could not register function");
+        interpreter.register("wrong", vec![], Some(Type::Int), |_| Ok(Some(Value::Bool(true))))
+            .expect("Could not register function");
+
+        interpreter.load("
+            function sum(n: int) -> int {
+                var total: int = 0;
+                while (n > 0) {
+                    total = add(total, n);
+                    n = n - 1;
+                }
+                return total;
+            }
+
+            function fail_twice() {
+                fail();
+            }
+
+            function wrong_twice() -> int {
+                return wrong() + wrong();
+            }
+        ").expect("Could not load source");
+
+        assert_eq!(interpreter.call("sum", vec![Value::Int(4)]), Ok(Some(Value::Int(10))));
+        assert_eq!(interpreter.call("add", vec![Value::Int(1), Value::Int(2)]), Ok(Some(Value::Int(3))));
+
+        let error = interpreter.call("add", vec![Value::Int(1), Value::Bool(true)])
+            .expect_err("The native should be type checked");
+        assert_eq!(error.kind, ErrorKind::TypeMismatch {
+            context: "argument 2 of function add".into(),
+            expected: Type::Int,
+            found: Type::Bool,
+        });
+
+        let error = interpreter.call("add", vec![]).expect_err("The native should be arity checked");
+        assert_eq!(error.kind, ErrorKind::Arity { function: "add".into(), expected: 2, found: 0 });
+
+        // Errors in natives are located at the call
+        let error = interpreter.call("fail_twice", vec![]).expect_err("The native should fail");
+        assert_eq!(error.kind, ErrorKind::Native("Out of coffee".into()));
+        let span = error.span.expect("The error should have a location");
+        assert_eq!((span.line, span.column), (12, 17));
+        assert_eq!(error.backtrace, vec!["fail_twice".to_string()]);
+
+        let error = interpreter.call("wrong_twice", vec![]).expect_err("The native returns the wrong type");
+        assert!(matches!(error.kind, ErrorKind::TypeMismatch { .. }));
+    }
+
+    #[test]
+    fn test_native_signatures() {
+        let mut interpreter = Interpreter::new();
+
+        interpreter.register("half", vec![Type::Int], Some(Type::Int), |args| match args {
+            [Value::Int(x)] => Ok(Some(Value::Int(x / 2))),
+            _ => unreachable!("The arguments are checked"),
+        }).expect("Could not register function");
+
+        for source in [
+            "function f() -> int { return half(true); }",
+            "function f() -> int { return half(1, 2); }",
+            "function f() -> bool { return half(1); }",
+            "function half(x: int) -> int { return x; }",
+        ] {
+            let error = interpreter.load(source).expect_err("The source should not pass the analysis");
+            assert!(matches!(error, LoadError::Invalid(_)), "{source}: {error:?}");
+        }
+
+        interpreter.load("function quarter(x: int) -> int { return half(half(x)); }").expect("Could not load source");
+        assert_eq!(interpreter.call("quarter", vec![Value::Int(20)]), Ok(Some(Value::Int(5))));
+    }
+
+    #[test]
+    fn test_register_defined() {
+        let mut interpreter = Interpreter::new();
+
+        assert_eq!(
+            interpreter.register("print", vec![Type::String], None, |_| Ok(None)),
+            Err(LoadError::Redefined("print".into())),
+        );
+
+        interpreter.register("f", vec![], None, |_| Ok(None)).expect("Could not register function");
+        assert_eq!(interpreter.register("f", vec![], None, |_| Ok(None)), Err(LoadError::Redefined("f".into())));
+
+        interpreter.load("function g() {}").expect("Could not load source");
+        assert_eq!(interpreter.register("g", vec![], None, |_| Ok(None)), Err(LoadError::Redefined("g".into())));
+    }
+
+    #[test]
+    fn test_output() {
+        let mut interpreter = Interpreter::new();
+
+        interpreter.load("
+            function greet(name: string, times: int) {
+                while (times > 0) {
+                    print(\"Hello \" + name + \" \");
+                    println(times);
+                    times = times - 1;
+                }
+                println(times == 0);
+            }
+        ").expect("Could not load source");
+
+        let mut output = vec![];
+        let result = interpreter.call_with_output("greet", vec![Value::String("KWLang".into()), Value::Int(2)], &mut output);
+
+        assert_eq!(result, Ok(None));
+        assert_eq!(String::from_utf8(output).unwrap(), "Hello KWLang 2\nHello KWLang 1\ntrue\n");
+
+        interpreter.load("function main() -> int { greet(\"bytecode\", 1); return 0; }").expect("Could not load source");
+
+        let mut output = vec![];
+        assert_eq!(interpreter.run_bytecode_with_output(&mut output), Ok(0));
+        assert_eq!(String::from_utf8(output).unwrap(), "Hello bytecode 1\ntrue\n");
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut interpreter = Interpreter::with_limits(Limits { max_statements: Some(10), ..Limits::default() });
+
+        interpreter.load("function spin() { while (true) {} }").expect("Could not load source");
+
+        let error = interpreter.call("spin", vec![]).expect_err("The loop should run out of statements");
+        assert_eq!(error.kind, ErrorKind::StatementLimit(10));
+    }
+
+    /// Sources nested to the default limit fit in the stack of every pass
+    /// and backend, and one level deeper they don't load.
+    #[test]
+    fn test_nesting() {
+        // The body of main, with the given number of levels
+        type Shape = fn(usize) -> String;
+
+        let shapes: [(&str, Shape); 7] = [
+            ("parentheses", |n| format!("return {}1{};", "(".repeat(n), ")".repeat(n))),
+            ("if-statements", |n| format!("{}{} return 1;", "if (true) {".repeat(n), "}".repeat(n))),
+            ("else branches", |n| format!("if (false) {{}} {}{{}} return 1;", "else if (false) {} ".repeat(n))),
+            ("unary operators", |n| format!("return {}1;", "- ".repeat(n))),
+            ("binary operators", |n| format!("return 1{};", " * 1".repeat(n))),
+            ("mixed operators", |n| format!("return 1{}{};", " * (1 + 1 * (1".repeat(n / 2), "))".repeat(n / 2))),
+            ("calls", |n| format!("return {}1{};", "id(".repeat(n), ")".repeat(n))),
+        ];
+        let max = Limits::default().max_nesting_depth;
+
+        for (shape, body) in shapes {
+            let source = |n| format!("function id(x: int) -> int {{ return x; }} function main() -> int {{ {} }}", body(n));
+
+            // The most levels of the shape that fit in the limit
+            let (mut fits, mut deeper) = (0, max + 1);
+            while deeper - fits > 1 {
+                let n = (fits + deeper) / 2;
+                match check_nesting(&source(n), max) {
+                    Ok(_) => fits = n,
+                    Err(_) => deeper = n,
+                }
+            }
+
+            let mut interpreter = Interpreter::new();
+            interpreter.load(&source(fits)).unwrap_or_else(|error| panic!("{shape}: {error}"));
+
+            let code = interpreter.run().unwrap_or_else(|error| panic!("{shape}: {error}"));
+            assert_eq!(interpreter.run_bytecode(), Ok(code), "{shape}");
+            interpreter.compile_riscv().unwrap_or_else(|error| panic!("{shape}: {error}"));
+
+            let error = Interpreter::new().load(&source(deeper)).expect_err("The source is nested too deeply");
+            assert!(matches!(error, LoadError::Nesting { .. }), "{shape}: {error}");
+        }
+    }
+
+    /// Recursion to the default call depth fits in the stack, with the calls nested in the functions.
+    #[test]
+    fn test_recursion() {
+        let mut interpreter = Interpreter::new();
+
+        interpreter.load("
+            function count(n: int) -> int {
+                if (n > 0) {
+                    while (true) {
+                        if (n > -1) {
+                            return 1 + (1 * (0 + count(n - 1) - 0));
+                        }
+                    }
+                }
+                return 0;
+            }
+        ").expect("Could not load source");
+
+        let depth = Limits::default().max_call_depth as i64;
+        assert_eq!(interpreter.call("count", vec![Value::Int(depth - 1)]), Ok(Some(Value::Int(depth - 1))));
+    }
+
+    #[test]
+    fn test_run() {
+        let mut interpreter = Interpreter::new();
+
+        interpreter.register("half", vec![Type::Int], Some(Type::Int), |args| match args {
+            [Value::Int(x)] => Ok(Some(Value::Int(x / 2))),
+            _ => unreachable!("The arguments are checked"),
+        }).expect("Could not register function");
+        interpreter.load("
+            function main() -> int {
+                return half(sum(10));
+            }
+
+            function sum(n: int) -> int {
+                if (n == 0) {
+                    return 0;
+                }
+                return n + sum(n - 1);
+            }
+        ").expect("Could not load source");
+
+        // Natives are called by both backends
+        assert_eq!(interpreter.run(), Ok(27));
+        assert_eq!(interpreter.run_bytecode(), Ok(27));
+
+        let mut interpreter = Interpreter::new();
+        interpreter.load("function main() -> bool { return true; }").expect("Could not load source");
+
+        let error = interpreter.run().expect_err("main has to return an int");
+        assert!(matches!(error.kind, ErrorKind::TypeMismatch { .. }));
+        assert_eq!(interpreter.run_bytecode(), Err(error));
+    }
+
+    #[test]
+    fn test_lints() {
+        let source = "function main() -> int { var x: int = 1; return 0; }";
+
+        let warnings = Interpreter::new().load(source).expect("Could not load source");
+        assert_eq!(warnings, vec![], "The lints only run when they are set");
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_lints(Config::default());
+        let warnings = interpreter.load(source).expect("Could not load source");
+        assert_eq!(warnings.len(), 1);
+
+        let mut config = Config::default();
+        config.set(Lint::UnusedVariable, Some(Severity::Error));
+        interpreter.set_lints(config);
+        interpreter.load(source).expect_err("The lint is an error");
+
+        // The functions of a library are called by the host
+        let library = "
+            function double(x: int) -> int { return x * 2; }
+            function triple(x: int) -> int { return x * 3; }
+        ";
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_lints(Config::default());
+        assert_eq!(interpreter.load(library), Ok(vec![]));
+
+        let mut config = Config::default();
+        config.add_entry_point("double");
+        let mut interpreter = Interpreter::new();
+        interpreter.set_lints(config);
+        let warnings = interpreter.load(library).expect("Could not load source");
+        assert_eq!(warnings.len(), 1);
+    }
+}
@@ -0,0 +1,25 @@
+mod parser;
+mod vm;
+mod compiler;
+mod typeck;
+mod resolver;
+mod flow;
+mod lint;
+mod bytecode;
+mod diagnostic;
+mod builtin;
+mod strings;
+mod interpreter;
+mod stack;
+
+extern crate pest_derive;
+extern crate from_pest;
+extern crate pest;
+
+pub use crate::diagnostic::{Diagnostic, Severity};
+pub use crate::interpreter::{Interpreter, LoadError};
+pub use crate::lint::{Config as LintConfig, Lint};
+pub use crate::parser::ast::{self, Program, Span, Type};
+pub use crate::vm::Limits;
+pub use crate::vm::error::{ErrorKind, RuntimeError};
+pub use crate::vm::value::Value;
@@ -0,0 +1,335 @@
+#[cfg(test)]
+mod test;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::diagnostic::Severity;
+use crate::parser::ast::{BinaryVerb, BlockNode, Expression, ExpressionNode, FunctionNode, Ident, Program, Span, Statement, TermNode};
+
+/// The lints that can be configured.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum Lint {
+    UnusedVariable,
+    UnusedParameter,
+    UnusedFunction,
+    NoEffect,
+    ConstantComparison,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 5] = [
+        Lint::UnusedVariable,
+        Lint::UnusedParameter,
+        Lint::UnusedFunction,
+        Lint::NoEffect,
+        Lint::ConstantComparison,
+    ];
+
+    /// The name used to refer to the lint on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::UnusedVariable => "unused-variable",
+            Self::UnusedParameter => "unused-parameter",
+            Self::UnusedFunction => "unused-function",
+            Self::NoEffect => "no-effect",
+            Self::ConstantComparison => "constant-comparison",
+        }
+    }
+}
+
+impl FromStr for Lint {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Lint::ALL
+            .into_iter()
+            .find(|lint| lint.name() == name)
+            .ok_or(format!("Unknown lint '{name}'"))
+    }
+}
+
+/// The severity of each lint, or `None` if it is allowed.
+///
+/// All lints are warnings by default.
+#[derive(Debug, Default, Clone)]
+pub struct Config {
+    levels: HashMap<Lint, Option<Severity>>,
+    /// The functions called from outside the program, which the other
+    /// functions have to be reachable from to be used.
+    entry_points: Vec<Ident>,
+}
+
+impl Config {
+    pub fn set(&mut self, lint: Lint, level: Option<Severity>) {
+        self.levels.insert(lint, level);
+    }
+
+    /// Add a function that is called from outside the program, like `main`
+    /// when running it, or the functions a host calls.
+    ///
+    /// Unused functions are only reported if there are entry points, since
+    /// any function of an embedded source may be called by the host.
+    pub fn add_entry_point(&mut self, name: &str) {
+        self.entry_points.push(name.into());
+    }
+
+    pub fn level(&self, lint: Lint) -> Option<Severity> {
+        self.levels.get(&lint).copied().unwrap_or(Some(Severity::Warning))
+    }
+}
+
+/// What a lint found.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Kind {
+    UnusedVariable(Ident),
+    UnusedParameter(Ident),
+    UnusedFunction(Ident),
+    NoEffect,
+    ConstantComparison(bool),
+}
+
+impl Kind {
+    pub fn lint(&self) -> Lint {
+        match self {
+            Self::UnusedVariable(_) => Lint::UnusedVariable,
+            Self::UnusedParameter(_) => Lint::UnusedParameter,
+            Self::UnusedFunction(_) => Lint::UnusedFunction,
+            Self::NoEffect => Lint::NoEffect,
+            Self::ConstantComparison(_) => Lint::ConstantComparison,
+        }
+    }
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnusedVariable(name) => write!(f, "Variable {name} is never read"),
+            Self::UnusedParameter(name) => write!(f, "Parameter {name} is never read"),
+            Self::UnusedFunction(name) => write!(f, "Function {name} is never called from an entry point"),
+            Self::NoEffect => write!(f, "Statement has no effect"),
+            Self::ConstantComparison(value) => {
+                write!(f, "Comparison with constant {value} can be replaced by the other operand")
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub kind: Kind,
+    pub span: Span,
+    pub severity: Severity,
+}
+
+impl Diagnostic {
+    /// Format the diagnostic with the location prefixed by the file name.
+    pub fn report(&self, file: &str) -> String {
+        format!("{file}:{}: {}: {} [{}]", self.span, self.severity, self.kind, self.kind.lint().name())
+    }
+}
+
+/// Run the lints that aren't allowed by the config on a resolved program.
+///
+/// Variables and parameters with names starting with `_` are never reported as unused.
+pub fn lint(program: &Program, config: &Config) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        findings: vec![],
+    };
+
+    linter.unused_functions(program, &config.entry_points);
+
+    for function in &program.nodes {
+        linter.lint_function(function);
+    }
+
+    linter.findings
+        .into_iter()
+        .filter_map(|(kind, span)| {
+            config.level(kind.lint()).map(|severity| Diagnostic { kind, span, severity })
+        })
+        .collect()
+}
+
+struct Linter {
+    findings: Vec<(Kind, Span)>,
+}
+
+impl Linter {
+    fn report(&mut self, kind: Kind, span: Span) {
+        self.findings.push((kind, span));
+    }
+
+    /// Report the functions that can't be reached from the entry points through calls.
+    fn unused_functions(&mut self, program: &Program, entry_points: &[Ident]) {
+        let functions: HashMap<&str, &FunctionNode> = program.nodes
+            .iter()
+            .map(|function| (function.name.as_str(), &**function))
+            .collect();
+
+        let mut queue: Vec<_> = entry_points.iter().filter_map(|name| functions.get(name.as_str()).copied()).collect();
+
+        // Without an entry point, there is nothing to start from
+        if queue.is_empty() {
+            return;
+        }
+
+        let mut reached: HashSet<_> = queue.iter().map(|function| function.name.as_str()).collect();
+
+        while let Some(function) = queue.pop() {
+            let mut calls = vec![];
+            visit_block(&function.block, &mut |expr| {
+                if let ExpressionNode::Call { name, .. } = &expr.node {
+                    calls.push(name.as_str());
+                }
+            });
+
+            for name in calls {
+                if let Some(callee) = functions.get(name) {
+                    if reached.insert(callee.name.as_str()) {
+                        queue.push(callee);
+                    }
+                }
+            }
+        }
+
+        for function in &program.nodes {
+            if !reached.contains(function.name.as_str()) {
+                self.report(Kind::UnusedFunction(function.name.clone()), function.span);
+            }
+        }
+    }
+
+    fn lint_function(&mut self, function: &FunctionNode) {
+        // Which slots are read anywhere in the function
+        let mut read = HashSet::new();
+
+        visit_block(&function.block, &mut |expr| {
+            if let ExpressionNode::Term(TermNode::Variable(variable)) = &expr.node {
+                read.extend(variable.slot);
+            }
+        });
+
+        for (slot, param) in function.parameters.iter().enumerate() {
+            if !read.contains(&slot) && !param.name.starts_with('_') {
+                self.report(Kind::UnusedParameter(param.name.clone()), param.span);
+            }
+        }
+
+        self.lint_block(&function.block, &read);
+    }
+
+    fn lint_block(&mut self, block: &[Statement], read: &HashSet<usize>) {
+        for node in block {
+            match &node.node {
+                BlockNode::VariableDefinition { name, value, .. } => {
+                    let unused = name.slot.is_some_and(|slot| !read.contains(&slot));
+
+                    if unused && !name.name.starts_with('_') {
+                        self.report(Kind::UnusedVariable(name.name.clone()), node.span);
+                    }
+
+                    self.lint_expression(value);
+                }
+                BlockNode::Expression(expr) => {
+                    if is_pure(expr) {
+                        self.report(Kind::NoEffect, node.span);
+                    }
+
+                    self.lint_expression(expr);
+                }
+                BlockNode::Assignment { rhs: expr, .. } | BlockNode::Return(expr) => self.lint_expression(expr),
+                BlockNode::Block(nested) => self.lint_block(nested, read),
+                BlockNode::IfStatement { condition, block, else_block } => {
+                    self.lint_expression(condition);
+                    self.lint_block(block, read);
+
+                    if let Some(else_block) = else_block {
+                        self.lint_block(else_block, read);
+                    }
+                }
+                BlockNode::WhileLoop { condition, block } => {
+                    self.lint_expression(condition);
+                    self.lint_block(block, read);
+                }
+                BlockNode::Break | BlockNode::Continue => {}
+            }
+        }
+    }
+
+    fn lint_expression(&mut self, expr: &Expression) {
+        visit_expression(expr, &mut |expr| {
+            if let ExpressionNode::BinaryOperation { verb: BinaryVerb::Compare | BinaryVerb::NotEqual, lhs, rhs } = &expr.node {
+                // Comparing two constants is reported once, for the constant on the right
+                let constant = [rhs, lhs].into_iter().find_map(|operand| match operand.node {
+                    ExpressionNode::Term(TermNode::Boolean(value)) => Some(value),
+                    _ => None,
+                });
+
+                if let Some(value) = constant {
+                    self.report(Kind::ConstantComparison(value), expr.span);
+                }
+            }
+        });
+    }
+}
+
+/// Whether evaluating the expression can't have any effect.
+///
+/// Only calls can have effects.
+fn is_pure(expr: &Expression) -> bool {
+    let mut pure = true;
+
+    visit_expression(expr, &mut |expr| {
+        if let ExpressionNode::Call { .. } = expr.node {
+            pure = false;
+        }
+    });
+
+    pure
+}
+
+/// Call `f` on every expression in the block, including sub-expressions.
+fn visit_block<'a>(block: &'a [Statement], f: &mut impl FnMut(&'a Expression)) {
+    for node in block {
+        match &node.node {
+            BlockNode::VariableDefinition { value: expr, .. }
+            | BlockNode::Assignment { rhs: expr, .. }
+            | BlockNode::Expression(expr)
+            | BlockNode::Return(expr) => visit_expression(expr, f),
+            BlockNode::Block(nested) => visit_block(nested, f),
+            BlockNode::IfStatement { condition, block, else_block } => {
+                visit_expression(condition, f);
+                visit_block(block, f);
+
+                if let Some(else_block) = else_block {
+                    visit_block(else_block, f);
+                }
+            }
+            BlockNode::WhileLoop { condition, block } => {
+                visit_expression(condition, f);
+                visit_block(block, f);
+            }
+            BlockNode::Break | BlockNode::Continue => {}
+        }
+    }
+}
+
+/// Call `f` on the expression and all its sub-expressions.
+fn visit_expression<'a>(expr: &'a Expression, f: &mut impl FnMut(&'a Expression)) {
+    f(expr);
+
+    match &expr.node {
+        ExpressionNode::BinaryOperation { lhs, rhs, .. } => {
+            visit_expression(lhs, f);
+            visit_expression(rhs, f);
+        }
+        ExpressionNode::Unary { operand, .. } => visit_expression(operand, f),
+        ExpressionNode::Call { args, .. } => {
+            for arg in args {
+                visit_expression(arg, f);
+            }
+        }
+        ExpressionNode::Term(_) => {}
+    }
+}
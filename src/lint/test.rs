@@ -0,0 +1,170 @@
+use crate::diagnostic::Severity;
+use crate::parser::parse;
+use crate::resolver::resolve;
+use super::{lint, Config, Kind, Lint};
+
+/// Parse, resolve and lint a program, and return the kinds and lines of the findings.
+fn findings(source: &str, config: &Config) -> Vec<(Kind, usize)> {
+    let mut program = parse(source).expect("Could not parse program");
+    resolve(&mut program).expect("Could not resolve program");
+
+    lint(&program, config)
+        .into_iter()
+        .map(|diagnostic| (diagnostic.kind, diagnostic.span.line))
+        .collect()
+}
+
+#[test]
+fn test_unused_variables() {
+    let findings = findings("
+        function main() -> int {
+            var x: int = 1;
+            var y: int = 2;
+            var _z: int = 3;
+            y = 4;
+            return add(x, 1);
+        }
+
+        function add(a: int, b: int) -> int {
+            return a;
+        }
+    ", &Config::default());
+
+    assert_eq!(
+        findings,
+        vec![
+            (Kind::UnusedVariable("y".into()), 4),
+            (Kind::UnusedParameter("b".into()), 10),
+        ],
+    );
+}
+
+#[test]
+fn test_unused_functions() {
+    let source = "
+        function main() -> int {
+            return a();
+        }
+
+        function a() -> int {
+            return b();
+        }
+
+        function b() -> int {
+            return a();
+        }
+
+        function c() -> int {
+            return c();
+        }
+    ";
+
+    assert_eq!(findings(source, &Config::default()), vec![], "Without entry points, any function may be used");
+
+    let mut config = Config::default();
+    config.add_entry_point("main");
+    assert_eq!(findings(source, &config), vec![(Kind::UnusedFunction("c".into()), 14)]);
+
+    // A library without main, with a function called by the host
+    config.add_entry_point("c");
+    assert_eq!(findings(source, &config), vec![]);
+
+    let mut config = Config::default();
+    config.add_entry_point("b");
+    config.add_entry_point("missing");
+    assert_eq!(
+        findings(source, &config),
+        vec![
+            (Kind::UnusedFunction("main".into()), 2),
+            (Kind::UnusedFunction("c".into()), 14),
+        ],
+    );
+}
+
+#[test]
+fn test_no_effect() {
+    let expression_findings = findings(include_str!("../../tests/expression.kw"), &Config::default());
+
+    assert_eq!(
+        expression_findings,
+        vec![
+            (Kind::NoEffect, 2),
+            (Kind::NoEffect, 3),
+            (Kind::NoEffect, 4),
+            (Kind::NoEffect, 5),
+        ],
+    );
+
+    let call_findings = findings("
+        function main() -> int {
+            1 + side_effect();
+            return 0;
+        }
+
+        function side_effect() -> int {
+            return 1;
+        }
+    ", &Config::default());
+
+    assert_eq!(call_findings, vec![]);
+}
+
+#[test]
+fn test_constant_comparison() {
+    let findings = findings("
+        function main() -> int {
+            var x: bool = true;
+
+            if (x == true || false != x) {
+                return 1;
+            }
+
+            return to_int(true == false);
+        }
+
+        function to_int(x: bool) -> int {
+            if (x) {
+                return 1;
+            }
+
+            return 0;
+        }
+    ", &Config::default());
+
+    assert_eq!(
+        findings,
+        vec![
+            (Kind::ConstantComparison(true), 5),
+            (Kind::ConstantComparison(false), 5),
+            (Kind::ConstantComparison(false), 9),
+        ],
+    );
+}
+
+#[test]
+fn test_config() {
+    let mut config = Config::default();
+    config.set(Lint::NoEffect, None);
+    config.set(Lint::UnusedVariable, Some(Severity::Error));
+
+    let mut program = parse("
+        function main() -> int {
+            var x: int = 1;
+            5 + 5;
+            return 0;
+        }
+    ").expect("Could not parse program");
+    resolve(&mut program).expect("Could not resolve program");
+
+    let diagnostics = lint(&program, &config);
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(
+        diagnostics[0].report("test.kw"),
+        "test.kw:3:13: error: Variable x is never read [unused-variable]",
+    );
+
+    assert_eq!("no-effect".parse(), Ok(Lint::NoEffect));
+    assert!("no-such-lint".parse::<Lint>().is_err());
+}
@@ -1,29 +1,31 @@
-mod parser;
-mod vm;
-mod compiler;
-
-extern crate pest_derive;
-extern crate from_pest;
-extern crate pest;
-
 use std::{env, fs};
 use std::path::{Path, PathBuf};
 use std::process::{Command, exit};
-use crate::compiler::compile;
-use crate::Operations::{Compile, Interpret};
-use crate::parser::parse;
-use crate::vm::run;
+use interpreter::{Interpreter, Lint, LintConfig, Severity};
+use crate::Operations::{Bytecode, Check, Compile, Interpret};
 
 fn print_usage(prog: &str) {
     println!("\
 USAGE: {prog} -i FILE
+       {prog} -b FILE
        {prog} -c FILE
-")
+       {prog} -l FILE [-A LINT | -W LINT | -D LINT]...
+
+  -i    Interpret the program by walking the syntax tree.
+  -b    Interpret the program by compiling it to bytecode.
+  -c    Compile the program to RISC-V assembly, and run it in QEMU.
+  -l    Check the program and print errors and warnings, without running it.
+        The lints can be allowed (-A), made warnings (-W) or made errors (-D).
+
+LINTS: {}
+", Lint::ALL.map(|lint| lint.name()).join(", "))
 }
 
 enum Operations {
     Interpret,
+    Bytecode,
     Compile,
+    Check(LintConfig),
 }
 
 fn main() {
@@ -33,8 +35,12 @@ fn main() {
         Some(arg) => {
             if *arg == "-i" {
                 Interpret
+            } else if *arg == "-b" {
+                Bytecode
             } else if *arg == "-c" {
                 Compile
+            } else if *arg == "-l" {
+                Check(parse_lint_config(args.get(3..).unwrap_or_default()))
             } else {
                 panic!("Invalid argument '{arg}'")
             }
@@ -49,34 +55,56 @@ fn main() {
     if let Some(source_path) = &args.get(2) {
         let input = fs::read_to_string(source_path).expect("Cannot read file");
 
-        match parse(&input) {
-            Ok(program) => {
-                println!("{program:#?}");
-
-                match operation {
-                    Interpret => {
-                        match run(&program) {
-                            Ok(return_value) => println!("Program returned {return_value}"),
-                            Err(error) => println!("Error during execution:\n{error}")
-                        }
-                    }
-                    Compile => {
-                        match compile(&program) {
-                            Ok(compiled) => {
-                                println!("Compiled assembly:\n{compiled}");
-                                let path = PathBuf::from(source_path);
-                                let asm_path = path.with_extension("s");
-
-                                fs::write(&asm_path, compiled).expect("Could not write assembly to file");
-
-                                run_compiled(&asm_path);
-                            },
-                            Err(error) => println!("Error during compilation:\n{error}"),
-                        }
-                    }
+        let mut interpreter = Interpreter::new();
+
+        if let Check(config) = &operation {
+            interpreter.set_lints(config.clone());
+        }
+
+        match interpreter.load(&input) {
+            Ok(warnings) => {
+                for warning in &warnings {
+                    println!("{}", warning.report(source_path));
+                }
+            }
+            Err(error) => {
+                println!("{}", error.report(source_path));
+                exit(-1);
+            }
+        }
+
+        match operation {
+            Check(_) => {}
+            Interpret => {
+                println!("{:#?}", interpreter.program());
+
+                match interpreter.run() {
+                    Ok(return_value) => println!("Program returned {return_value}"),
+                    Err(error) => println!("Error during execution:\n{}", error.report(source_path))
+                }
+            }
+            Bytecode => {
+                match interpreter.run_bytecode() {
+                    Ok(return_value) => println!("Program returned {return_value}"),
+                    Err(error) => println!("Error during execution:\n{}", error.report(source_path))
+                }
+            }
+            Compile => {
+                println!("{:#?}", interpreter.program());
+
+                match interpreter.compile_riscv() {
+                    Ok(compiled) => {
+                        println!("Compiled assembly:\n{compiled}");
+                        let path = PathBuf::from(source_path);
+                        let asm_path = path.with_extension("s");
+
+                        fs::write(&asm_path, compiled).expect("Could not write assembly to file");
+
+                        run_compiled(&asm_path);
+                    },
+                    Err(error) => println!("Error during compilation:\n{error}"),
                 }
             }
-            Err(error) => println!("{error}")
         }
     } else {
         print_usage(&args[0]);
@@ -85,6 +113,28 @@ fn main() {
     }
 }
 
+/// Parse the `-A`, `-W` and `-D` options that set the level of lints.
+fn parse_lint_config(args: &[String]) -> LintConfig {
+    let mut config = LintConfig::default();
+    // The program is run from main
+    config.add_entry_point("main");
+
+    for option in args.chunks(2) {
+        let level = match option[0].as_str() {
+            "-A" => None,
+            "-W" => Some(Severity::Warning),
+            "-D" => Some(Severity::Error),
+            arg => panic!("Invalid argument '{arg}'"),
+        };
+
+        let lint = option.get(1).expect("Expected a lint name").parse().unwrap_or_else(|error| panic!("{error}"));
+
+        config.set(lint, level);
+    }
+
+    config
+}
+
 fn run_compiled(path: &Path) {
     let bin_path = path.with_extension("bin");
 
@@ -103,7 +153,7 @@ fn run_compiled(path: &Path) {
     println!("Running gcc");
     let gcc_result = Command::new(gcc)
         .args(["-g", "-Wl,-T,linker.lds", "-nostartfiles", "-nostdlib"])
-        .arg(&path)
+        .arg(path)
         .arg("-o")
         .arg(&bin_path)
         .status()
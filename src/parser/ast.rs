@@ -1,8 +1,11 @@
 use std::fmt::{Display, Formatter};
+use std::ops::Deref;
+use std::sync::Arc;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, Default, PartialEq, Clone)]
 pub struct Program {
-    pub nodes: Vec<FunctionNode>
+    /// The functions are shared, so that running the program doesn't copy them.
+    pub nodes: Vec<Arc<FunctionNode>>
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -10,31 +13,134 @@ pub struct FunctionNode {
     pub name: Ident,
     pub parameters: Vec<FunctionParam>,
     pub return_type: Option<Type>,
-    pub block: Vec<BlockNode>,
+    pub block: Vec<Statement>,
+    /// The text of the doc comments (`///`) before the function, if any.
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+impl FunctionNode {
+    /// How deeply the statements and expressions of the function are nested.
+    ///
+    /// The tree-walker recurses for each level, so this bounds the stack a
+    /// call needs.
+    pub fn depth(&self) -> usize {
+        let mut deepest = 0;
+        self.walk(|_, depth| deepest = deepest.max(depth));
+        deepest
+    }
+
+    /// The functions called by the function, with how deeply each call is nested.
+    pub fn calls(&self) -> Vec<(&str, usize)> {
+        let mut calls = vec![];
+
+        self.walk(|expr, depth| {
+            if let Some(ExpressionNode::Call { name, .. }) = expr {
+                calls.push((name.as_str(), depth));
+            }
+        });
+
+        calls
+    }
+
+    /// Visit the statements and expressions with their depth, without
+    /// recursing. Statements are visited as `None`.
+    fn walk<'a, F: FnMut(Option<&'a ExpressionNode>, usize)>(&'a self, mut visit: F) {
+        enum Node<'a> {
+            Statement(&'a Statement),
+            Expression(&'a Expression),
+        }
+
+        let mut pending: Vec<_> = self.block.iter().map(|statement| (Node::Statement(statement), 1)).collect();
+
+        while let Some((node, depth)) = pending.pop() {
+            let next = depth + 1;
+
+            match node {
+                Node::Statement(statement) => {
+                    visit(None, depth);
+
+                    let (expr, blocks) = match &statement.node {
+                        BlockNode::VariableDefinition { value: expr, .. }
+                        | BlockNode::Assignment { rhs: expr, .. }
+                        | BlockNode::Expression(expr)
+                        | BlockNode::Return(expr) => (Some(expr), [None, None]),
+                        BlockNode::Block(block) => (None, [Some(block), None]),
+                        BlockNode::IfStatement { condition, block, else_block } => {
+                            (Some(condition), [Some(block), else_block.as_ref()])
+                        }
+                        BlockNode::WhileLoop { condition, block } => (Some(condition), [Some(block), None]),
+                        BlockNode::Break | BlockNode::Continue => (None, [None, None]),
+                    };
+
+                    pending.extend(expr.map(|expr| (Node::Expression(expr), next)));
+                    pending.extend(blocks.into_iter().flatten().flatten().map(|statement| (Node::Statement(statement), next)));
+                }
+                Node::Expression(expr) => {
+                    visit(Some(&expr.node), depth);
+
+                    match &expr.node {
+                        ExpressionNode::BinaryOperation { lhs, rhs, .. } => {
+                            pending.push((Node::Expression(lhs), next));
+                            pending.push((Node::Expression(rhs), next));
+                        }
+                        ExpressionNode::Unary { operand, .. } => pending.push((Node::Expression(operand), next)),
+                        ExpressionNode::Call { args, .. } => pending.extend(args.iter().map(|arg| (Node::Expression(arg), next))),
+                        ExpressionNode::Term(_) => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The name and types of a function, for functions that are defined
+/// outside the program.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Signature {
+    pub name: Ident,
+    pub parameters: Vec<Type>,
+    pub return_type: Option<Type>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct FunctionParam {
     pub name: Ident,
     pub type_name: Type,
+    pub span: Span,
 }
 
+pub type Statement = Spanned<BlockNode>;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum BlockNode {
-    VariableDefinition { name: Ident, type_name: Type, value: ExpressionNode },
-    Assignment { lhs: Ident, rhs: ExpressionNode },
-    Expression(ExpressionNode),
-    Block(Vec<BlockNode>),
-    IfStatement { condition: ExpressionNode, block: Vec<BlockNode> },
-    Return(ExpressionNode),
+    VariableDefinition { name: Variable, type_name: Type, value: Expression },
+    Assignment { lhs: Variable, rhs: Expression },
+    Expression(Expression),
+    Block(Vec<Statement>),
+    IfStatement { condition: Expression, block: Vec<Statement>, else_block: Option<Vec<Statement>> },
+    WhileLoop { condition: Expression, block: Vec<Statement> },
+    Break,
+    Continue,
+    Return(Expression),
 }
 
+pub type Expression = Spanned<ExpressionNode>;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum ExpressionNode {
     BinaryOperation {
         verb: BinaryVerb,
-        lhs: Box<ExpressionNode>,
-        rhs: Box<ExpressionNode>,
+        lhs: Box<Expression>,
+        rhs: Box<Expression>,
+    },
+    Unary {
+        op: UnaryVerb,
+        operand: Box<Expression>,
+    },
+    Call {
+        name: Ident,
+        args: Vec<Expression>,
     },
     Term(TermNode),
 }
@@ -43,14 +149,71 @@ pub enum ExpressionNode {
 pub enum BinaryVerb {
     Plus,
     Minus,
+    Multiply,
+    Divide,
+    Modulo,
+    /// The wrapping operators `+%`, `-%` and `*%`, which wrap around on
+    /// overflow instead of failing.
+    WrappingPlus,
+    WrappingMinus,
+    WrappingMultiply,
     Compare,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+}
+
+impl BinaryVerb {
+    /// The operator as written in the source.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Plus => "+",
+            Self::Minus => "-",
+            Self::Multiply => "*",
+            Self::Divide => "/",
+            Self::Modulo => "%",
+            Self::WrappingPlus => "+%",
+            Self::WrappingMinus => "-%",
+            Self::WrappingMultiply => "*%",
+            Self::Compare => "==",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::LessEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterEqual => ">=",
+            Self::And => "&&",
+            Self::Or => "||",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum UnaryVerb {
+    Plus,
+    Minus,
+    Not,
+}
+
+impl UnaryVerb {
+    /// The operator as written in the source.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Plus => "+",
+            Self::Minus => "-",
+            Self::Not => "!",
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum TermNode {
-    Variable(Ident),
+    Variable(Variable),
     Boolean(bool),
-    Integer(i32),
+    Integer(i64),
     String(String),
 }
 
@@ -72,3 +235,92 @@ impl Display for Type {
 }
 
 pub type Ident = String;
+
+/// A definition or use of a local variable.
+///
+/// The resolver gives every variable definition in a function its own slot
+/// in the frame of the function, and links every use to the slot of its
+/// definition. The parameters of a function use the first slots, in order.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Variable {
+    pub name: Ident,
+    /// The slot in the frame, or `None` if the variable hasn't been resolved.
+    pub slot: Option<usize>,
+}
+
+impl From<Ident> for Variable {
+    fn from(name: Ident) -> Self {
+        Variable { name, slot: None }
+    }
+}
+
+impl From<&str> for Variable {
+    fn from(name: &str) -> Self {
+        name.to_string().into()
+    }
+}
+
+impl Display for Variable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// The location of a node in the source code.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct Span {
+    /// The byte offset of the start of the node.
+    pub start: usize,
+    /// The byte offset just after the end of the node.
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    /// The smallest span covering both spans.
+    pub fn to(&self, other: &Span) -> Span {
+        let first = if self.start <= other.start { self } else { other };
+
+        Span {
+            start: first.start,
+            end: self.end.max(other.end),
+            line: first.line,
+            column: first.column,
+        }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A node with the location it was parsed from.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.node
+    }
+}
+
+/// Nodes created outside the parser get an empty span.
+impl<T> From<T> for Spanned<T> {
+    fn from(node: T) -> Self {
+        Spanned { node, span: Span::default() }
+    }
+}
+
+impl From<ExpressionNode> for Box<Expression> {
+    fn from(node: ExpressionNode) -> Self {
+        Box::new(node.into())
+    }
+}
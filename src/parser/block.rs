@@ -2,7 +2,7 @@ use pest::iterators::Pair;
 use crate::parser::utils::parse_all;
 use super::ast::BlockNode;
 use super::error::ParseError;
-use super::from_pest::FromPest;
+use super::from_pest::{FromPest, ParsePest};
 use super::Rule;
 use super::utils::parse_next;
 
@@ -15,7 +15,7 @@ impl FromPest<'_> for BlockNode {
 
                 Ok(
                     BlockNode::VariableDefinition {
-                        name,
+                        name: name.into(),
                         type_name,
                         value: parse_next(&mut inner, &pair)?,
                     }
@@ -52,9 +52,27 @@ impl FromPest<'_> for BlockNode {
                     BlockNode::IfStatement {
                         condition: parse_next(&mut inner, &pair)?,
                         block: parse_next(&mut inner, &pair)?,
+                        // An else-if is stored as an else block containing only the next if-statement
+                        else_block: match inner.next() {
+                            Some(next) if next.as_rule() == Rule::if_statement => Some(vec![next.parse()?]),
+                            Some(next) => Some(next.parse()?),
+                            None => None,
+                        },
                     }
                 )
-            }
+            },
+            Rule::while_loop => {
+                let mut inner = pair.clone().into_inner();
+
+                Ok(
+                    BlockNode::WhileLoop {
+                        condition: parse_next(&mut inner, &pair)?,
+                        block: parse_next(&mut inner, &pair)?,
+                    }
+                )
+            },
+            Rule::break_stmt => Ok(BlockNode::Break),
+            Rule::continue_stmt => Ok(BlockNode::Continue),
             rule => Err(ParseError::wrong_rule(&pair, rule))
         }
     }
@@ -77,8 +95,8 @@ mod test {
                 type_name: Type::Int,
                 value: ExpressionNode::Term(
                     TermNode::Integer(5)
-                )
-            }
+                ).into()
+            }.into()
         ];
 
         assert_eq!(nodes, expected);
@@ -97,14 +115,14 @@ mod test {
                 type_name: Type::Int,
                 value: ExpressionNode::Term(
                     TermNode::Integer(5)
-                )
-            },
+                ).into()
+            }.into(),
             BlockNode::Assignment {
                 lhs: "x".into(),
                 rhs: ExpressionNode::Term(
                     TermNode::Integer(9)
-                )
-            }
+                ).into()
+            }.into()
         ];
 
         assert_eq!(nodes, expected);
@@ -120,8 +138,30 @@ mod test {
             BlockNode::Return(
                 ExpressionNode::Term(
                     TermNode::Integer(5)
-                )
-            ),
+                ).into()
+            ).into(),
+        ];
+
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn test_keyword_prefix() {
+        let nodes = parse_block("
+            breakfast;
+            continue_x;
+            returned = 1;
+            return(1);
+        ");
+
+        let expected = vec![
+            BlockNode::Expression(ExpressionNode::Term(TermNode::Variable("breakfast".into())).into()).into(),
+            BlockNode::Expression(ExpressionNode::Term(TermNode::Variable("continue_x".into())).into()).into(),
+            BlockNode::Assignment {
+                lhs: "returned".into(),
+                rhs: ExpressionNode::Term(TermNode::Integer(1)).into(),
+            }.into(),
+            BlockNode::Return(ExpressionNode::Term(TermNode::Integer(1)).into()).into(),
         ];
 
         assert_eq!(nodes, expected);
@@ -135,7 +175,7 @@ mod test {
         ");
 
         let expected = vec![
-            BlockNode::Block(vec![]),
+            BlockNode::Block(vec![]).into(),
         ];
 
         assert_eq!(nodes, expected);
@@ -158,8 +198,8 @@ mod test {
                 type_name: Type::Int,
                 value: ExpressionNode::Term(
                     TermNode::Integer(5)
-                )
-            },
+                ).into()
+            }.into(),
 
             BlockNode::Block(vec![
                 BlockNode::VariableDefinition {
@@ -167,24 +207,24 @@ mod test {
                     type_name: Type::Int,
                     value: ExpressionNode::Term(
                         TermNode::Integer(10)
-                    )
-                },
+                    ).into()
+                }.into(),
 
                 BlockNode::Block(vec![
                     BlockNode::Assignment {
                         lhs: "x".into(),
                         rhs: ExpressionNode::Term(
                             TermNode::Variable("y".into())
-                        )
-                    }
-                ]),
+                        ).into()
+                    }.into()
+                ]).into(),
 
                 BlockNode::Return(
                     ExpressionNode::Term(
                         TermNode::Variable("x".into())
-                    )
-                ),
-            ]),
+                    ).into()
+                ).into(),
+            ]).into(),
         ];
 
         assert_eq!(nodes, expected);
@@ -205,23 +245,112 @@ mod test {
                 type_name: Type::Int,
                 value: ExpressionNode::Term(
                     TermNode::Integer(5)
-                )
-            },
+                ).into()
+            }.into(),
 
             BlockNode::IfStatement {
                 condition: ExpressionNode::BinaryOperation {
                     verb: BinaryVerb::Compare,
-                    lhs: ExpressionNode::Term(TermNode::Variable("x".to_string())).into(),
+                    lhs: ExpressionNode::Term(TermNode::Variable("x".into())).into(),
                     rhs: ExpressionNode::Term(TermNode::Integer(5)).into(),
-                },
+                }.into(),
                 block: vec![
                     BlockNode::Return(
                         ExpressionNode::Term(
                             TermNode::Variable("x".into())
-                        )
-                    ),
+                        ).into()
+                    ).into(),
                 ],
-            },
+                else_block: None,
+            }.into(),
+        ];
+
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn test_else() {
+        let nodes = parse_block("
+            if (x == 5) {
+                return 1;
+            } else if (x == 6) {
+                return 2;
+            } else {
+                return 3;
+            }
+            if (x == 7) {} else {}
+        ");
+
+        let expected = vec![
+            BlockNode::IfStatement {
+                condition: ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Compare,
+                    lhs: ExpressionNode::Term(TermNode::Variable("x".into())).into(),
+                    rhs: ExpressionNode::Term(TermNode::Integer(5)).into(),
+                }.into(),
+                block: vec![
+                    BlockNode::Return(ExpressionNode::Term(TermNode::Integer(1)).into()).into(),
+                ],
+                else_block: Some(vec![
+                    BlockNode::IfStatement {
+                        condition: ExpressionNode::BinaryOperation {
+                            verb: BinaryVerb::Compare,
+                            lhs: ExpressionNode::Term(TermNode::Variable("x".into())).into(),
+                            rhs: ExpressionNode::Term(TermNode::Integer(6)).into(),
+                        }.into(),
+                        block: vec![
+                            BlockNode::Return(ExpressionNode::Term(TermNode::Integer(2)).into()).into(),
+                        ],
+                        else_block: Some(vec![
+                            BlockNode::Return(ExpressionNode::Term(TermNode::Integer(3)).into()).into(),
+                        ]),
+                    }.into(),
+                ]),
+            }.into(),
+            BlockNode::IfStatement {
+                condition: ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Compare,
+                    lhs: ExpressionNode::Term(TermNode::Variable("x".into())).into(),
+                    rhs: ExpressionNode::Term(TermNode::Integer(7)).into(),
+                }.into(),
+                block: vec![],
+                else_block: Some(vec![]),
+            }.into(),
+        ];
+
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn test_while_loop() {
+        let nodes = parse_block("
+            while (x == 5) {
+                x = x + 1;
+                continue;
+                break;
+            }
+        ");
+
+        let expected = vec![
+            BlockNode::WhileLoop {
+                condition: ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Compare,
+                    lhs: ExpressionNode::Term(TermNode::Variable("x".into())).into(),
+                    rhs: ExpressionNode::Term(TermNode::Integer(5)).into(),
+                }.into(),
+                block: vec![
+                    BlockNode::Assignment {
+                        lhs: "x".into(),
+                        rhs: ExpressionNode::BinaryOperation {
+                            verb: BinaryVerb::Plus,
+                            lhs: ExpressionNode::Term(TermNode::Variable("x".into())).into(),
+                            rhs: ExpressionNode::Term(TermNode::Integer(1)).into(),
+                        }.into(),
+                    }.into(),
+                    BlockNode::Continue.into(),
+                    BlockNode::Break.into(),
+                ],
+            }.into(),
         ];
 
         assert_eq!(nodes, expected);
@@ -1,5 +1,5 @@
 use pest::iterators::Pair;
-use super::ast::{Type, Ident};
+use super::ast::{Type, Ident, Span, Spanned, Variable};
 use super::error::ParseError;
 use super::from_pest::{FromPest, ParsePest};
 use super::Rule;
@@ -34,6 +34,13 @@ impl FromPest<'_> for Ident {
     }
 }
 
+impl FromPest<'_> for Variable {
+    #[track_caller]
+    fn from_pest(pair: Pair<'_, Rule>) -> Result<Self, ParseError> {
+        Ok(Ident::from_pest(pair)?.into())
+    }
+}
+
 impl FromPest<'_> for (Ident, Type) {
     fn from_pest(pair: Pair<'_, Rule>) -> Result<Self, ParseError> {
         match pair.as_rule() {
@@ -57,3 +64,24 @@ impl<'pest, T: FromPest<'pest>> FromPest<'pest> for Box<T> {
         Ok(Box::new(pair.parse()?))
     }
 }
+
+impl From<&Pair<'_, Rule>> for Span {
+    fn from(pair: &Pair<'_, Rule>) -> Self {
+        let (line, column) = pair.line_col();
+
+        Span {
+            start: pair.as_span().start(),
+            end: pair.as_span().end(),
+            line,
+            column,
+        }
+    }
+}
+
+impl<'pest, T: FromPest<'pest>> FromPest<'pest> for Spanned<T> {
+    fn from_pest(pair: Pair<'pest, Rule>) -> Result<Self, ParseError> {
+        let span = Span::from(&pair);
+
+        Ok(Spanned { node: pair.parse()?, span })
+    }
+}
@@ -3,6 +3,7 @@ use std::panic::Location;
 use itertools::Itertools;
 use pest::iterators::Pair;
 use pest::RuleType;
+use super::ast::Span;
 
 pub struct LineInfo {
     position: (usize, usize),
@@ -60,6 +61,15 @@ pub enum ParseError {
         caller: CallerInfo,
         rule: String,
     },
+    /// An integer literal that doesn't fit in an int.
+    ///
+    /// This is its own variant so that the negation of the literal can
+    /// still be accepted if it is in range.
+    IntegerOutOfRange {
+        literal: String,
+        line: LineInfo,
+        span: Span,
+    },
     ErrorList(Vec<ParseError>),
 }
 
@@ -117,6 +127,9 @@ impl Display for ParseError {
             Self::UnexpectedRule {line, caller, rule} => {
                 write!(f, "{line}: Unexpected rule {rule}. Called from {caller}")
             }
+            Self::IntegerOutOfRange {literal, line, ..} => {
+                write!(f, "{line}\n\tInteger literal {literal} is out of range")
+            }
             Self::ErrorList(list) => {
                 list.iter().try_for_each(|e| e.fmt(f))
             }
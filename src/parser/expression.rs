@@ -1,21 +1,105 @@
+use std::sync::OnceLock;
 use pest::iterators::Pair;
-use super::ast::{BinaryVerb, ExpressionNode, TermNode};
+use pest::pratt_parser::{Assoc, Op, PrattParser};
+use super::ast::{BinaryVerb, Expression, ExpressionNode, Span, Spanned, TermNode, UnaryVerb};
 use super::error::ParseError;
 use super::from_pest::{FromPest, ParsePest};
 use super::Rule;
 use super::utils::{map_next, parse_next};
 
+/// The operator precedence, from lowest to highest.
+///
+/// All binary operators are left associative, and unary operators bind
+/// tighter than any binary operator.
+fn pratt_parser() -> &'static PrattParser<Rule> {
+    static PARSER: OnceLock<PrattParser<Rule>> = OnceLock::new();
+
+    PARSER.get_or_init(||
+        PrattParser::new()
+            .op(Op::infix(Rule::or, Assoc::Left))
+            .op(Op::infix(Rule::and, Assoc::Left))
+            .op(Op::infix(Rule::compare, Assoc::Left) | Op::infix(Rule::not_equal, Assoc::Left))
+            .op(
+                Op::infix(Rule::less, Assoc::Left)
+                | Op::infix(Rule::less_equal, Assoc::Left)
+                | Op::infix(Rule::greater, Assoc::Left)
+                | Op::infix(Rule::greater_equal, Assoc::Left)
+            )
+            .op(
+                Op::infix(Rule::add, Assoc::Left)
+                | Op::infix(Rule::subtract, Assoc::Left)
+                | Op::infix(Rule::wrapping_add, Assoc::Left)
+                | Op::infix(Rule::wrapping_subtract, Assoc::Left)
+            )
+            .op(
+                Op::infix(Rule::multiply, Assoc::Left)
+                | Op::infix(Rule::wrapping_multiply, Assoc::Left)
+                | Op::infix(Rule::divide, Assoc::Left)
+                | Op::infix(Rule::modulo, Assoc::Left)
+            )
+            .op(Op::prefix(Rule::not) | Op::prefix(Rule::negative) | Op::prefix(Rule::positive))
+    )
+}
+
 impl FromPest<'_> for ExpressionNode {
     fn from_pest(pair: Pair<'_, Rule>) -> Result<Self, ParseError> {
         match pair.as_rule() {
-            Rule::binary_op => {
+            Rule::expr => {
+                pratt_parser()
+                    .map_primary(|primary| primary.parse())
+                    .map_prefix(|op, operand: Result<Expression, ParseError>| {
+                        let span = Span::from(&op);
+
+                        match (op.as_rule(), operand) {
+                            // The smallest int can only be written as a negated literal
+                            (Rule::negative, Err(ParseError::IntegerOutOfRange { literal, line, span: literal_span })) => {
+                                format!("-{literal}")
+                                    .parse()
+                                    .map(|x| Spanned {
+                                        node: ExpressionNode::Term(TermNode::Integer(x)),
+                                        span: span.to(&literal_span),
+                                    })
+                                    .map_err(|_| ParseError::IntegerOutOfRange { literal, line, span: literal_span })
+                            }
+                            (_, operand) => {
+                                let operand = operand?;
+
+                                Ok(
+                                    Spanned {
+                                        span: span.to(&operand.span),
+                                        node: ExpressionNode::Unary {
+                                            op: op.parse()?,
+                                            operand: Box::new(operand),
+                                        },
+                                    }
+                                )
+                            }
+                        }
+                    })
+                    .map_infix(|lhs, verb, rhs| {
+                        let (lhs, rhs) = (lhs?, rhs?);
+
+                        Ok(
+                            Spanned {
+                                span: lhs.span.to(&rhs.span),
+                                node: ExpressionNode::BinaryOperation {
+                                    verb: verb.parse()?,
+                                    lhs: Box::new(lhs),
+                                    rhs: Box::new(rhs),
+                                },
+                            }
+                        )
+                    })
+                    .parse(pair.into_inner())
+                    .map(|expr| expr.node)
+            }
+            Rule::call => {
                 let mut inner = pair.clone().into_inner();
 
                 Ok(
-                    ExpressionNode::BinaryOperation {
-                        lhs: parse_next(&mut inner, &pair)?,
-                        verb: parse_next(&mut inner, &pair)?,
-                        rhs: parse_next(&mut inner, &pair)?,
+                    ExpressionNode::Call {
+                        name: parse_next(&mut inner, &pair)?,
+                        args: parse_next(&mut inner, &pair)?,
                     }
                 )
             }
@@ -41,11 +125,28 @@ impl FromPest<'_> for ExpressionNode {
             }
             Rule::str => {
                 let mut inner = pair.clone().into_inner();
+                let inner_str = inner.next().ok_or(ParseError::end(&pair))?;
+
+                // Report every invalid escape, not just the first
+                let parts = ParseError::merge(inner_str.into_inner().map(|part| match part.as_rule() {
+                    Rule::text => Ok(part.as_str().to_string()),
+                    Rule::escape => unescape(&part).map(String::from),
+                    rule => Err(ParseError::wrong_rule(&part, rule)),
+                }))?;
+
+                Ok(
+                    ExpressionNode::Term(
+                        TermNode::String(parts.concat())
+                    )
+                )
+            }
+            Rule::raw_str => {
+                let mut inner = pair.clone().into_inner();
 
                 Ok(
                     ExpressionNode::Term(
                         TermNode::String(
-                            // Get the inner text to avoid the literal "s
+                            // Get the inner text to avoid the r" and "
                             map_next(&mut inner, &pair, |p| p.as_str().to_string())?
                         )
                     )
@@ -56,7 +157,11 @@ impl FromPest<'_> for ExpressionNode {
                     ExpressionNode::Term(
                         TermNode::Integer(
                             pair.as_str().parse().map_err(|_|
-                                ParseError::from_pair(&pair, format!("Can't convert {} to int", pair.as_str()))
+                                ParseError::IntegerOutOfRange {
+                                    literal: pair.as_str().to_string(),
+                                    line: (&pair).into(),
+                                    span: (&pair).into(),
+                                }
                             )?
                         )
                     )
@@ -67,12 +172,57 @@ impl FromPest<'_> for ExpressionNode {
     }
 }
 
+/// The character of an escape sequence in a string literal.
+fn unescape(pair: &Pair<'_, Rule>) -> Result<char, ParseError> {
+    let sequence = pair.as_str();
+
+    match sequence {
+        "\\n" => Ok('\n'),
+        "\\t" => Ok('\t'),
+        "\\\\" => Ok('\\'),
+        "\\\"" => Ok('"'),
+        "\\u" => Err(ParseError::from_pair(pair, "Unicode escapes must be written as \\u{...}")),
+        _ => match sequence.strip_prefix("\\u{").and_then(|code| code.strip_suffix('}')) {
+            Some(code) => Some(code)
+                .filter(|code| (1..=6).contains(&code.len()) && code.chars().all(|c| c.is_ascii_hexdigit()))
+                .and_then(|code| u32::from_str_radix(code, 16).ok())
+                .and_then(char::from_u32)
+                .ok_or_else(|| ParseError::from_pair(pair, format!("Invalid unicode escape {sequence}"))),
+            None => Err(ParseError::from_pair(pair, format!("Invalid escape sequence {sequence}"))),
+        },
+    }
+}
+
 impl FromPest<'_> for BinaryVerb {
     fn from_pest(pair: Pair<'_, Rule>) -> Result<Self, ParseError> {
         match pair.as_rule() {
             Rule::add => Ok(BinaryVerb::Plus),
             Rule::subtract => Ok(BinaryVerb::Minus),
+            Rule::multiply => Ok(BinaryVerb::Multiply),
+            Rule::divide => Ok(BinaryVerb::Divide),
+            Rule::modulo => Ok(BinaryVerb::Modulo),
+            Rule::wrapping_add => Ok(BinaryVerb::WrappingPlus),
+            Rule::wrapping_subtract => Ok(BinaryVerb::WrappingMinus),
+            Rule::wrapping_multiply => Ok(BinaryVerb::WrappingMultiply),
             Rule::compare => Ok(BinaryVerb::Compare),
+            Rule::not_equal => Ok(BinaryVerb::NotEqual),
+            Rule::less => Ok(BinaryVerb::Less),
+            Rule::less_equal => Ok(BinaryVerb::LessEqual),
+            Rule::greater => Ok(BinaryVerb::Greater),
+            Rule::greater_equal => Ok(BinaryVerb::GreaterEqual),
+            Rule::and => Ok(BinaryVerb::And),
+            Rule::or => Ok(BinaryVerb::Or),
+            rule => Err(ParseError::wrong_rule(&pair, rule))
+        }
+    }
+}
+
+impl FromPest<'_> for UnaryVerb {
+    fn from_pest(pair: Pair<'_, Rule>) -> Result<Self, ParseError> {
+        match pair.as_rule() {
+            Rule::not => Ok(UnaryVerb::Not),
+            Rule::negative => Ok(UnaryVerb::Minus),
+            Rule::positive => Ok(UnaryVerb::Plus),
             rule => Err(ParseError::wrong_rule(&pair, rule))
         }
     }
@@ -82,6 +232,7 @@ impl FromPest<'_> for BinaryVerb {
 mod test {
     use super::super::{
         ast::*,
+        parse,
         test::helper::*,
     };
 
@@ -94,10 +245,13 @@ mod test {
 
         let expected = vec![
             BlockNode::Expression(
-                ExpressionNode::Term(
-                    TermNode::Integer(5)
-                )
-            ),
+                ExpressionNode::Unary {
+                    op: UnaryVerb::Plus,
+                    operand: ExpressionNode::Term(
+                        TermNode::Integer(5)
+                    ).into(),
+                }.into()
+            ).into(),
             BlockNode::Expression (
                 ExpressionNode::BinaryOperation {
                     verb: BinaryVerb::Plus,
@@ -113,8 +267,233 @@ mod test {
                     rhs: ExpressionNode::Term(
                         TermNode::Integer(8)
                     ).into()
-                }
-            ),
+                }.into()
+            ).into(),
+        ];
+
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn test_expr_chained() {
+        let nodes = parse_block("
+            1 + 2 + 3;
+            1 - 2 + 3;
+        ");
+
+        let expected = vec![
+            BlockNode::Expression(
+                ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Plus,
+                    lhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Plus,
+                        lhs: ExpressionNode::Term(TermNode::Integer(1)).into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(2)).into(),
+                    }.into(),
+                    rhs: ExpressionNode::Term(TermNode::Integer(3)).into(),
+                }.into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Plus,
+                    lhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Minus,
+                        lhs: ExpressionNode::Term(TermNode::Integer(1)).into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(2)).into(),
+                    }.into(),
+                    rhs: ExpressionNode::Term(TermNode::Integer(3)).into(),
+                }.into()
+            ).into(),
+        ];
+
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn test_expr_precedence() {
+        let nodes = parse_block("
+            1 + 2 == 4 - 1;
+            x == y == true;
+            (1 + 2) - (3 - 4);
+        ");
+
+        let expected = vec![
+            BlockNode::Expression(
+                ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Compare,
+                    lhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Plus,
+                        lhs: ExpressionNode::Term(TermNode::Integer(1)).into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(2)).into(),
+                    }.into(),
+                    rhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Minus,
+                        lhs: ExpressionNode::Term(TermNode::Integer(4)).into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(1)).into(),
+                    }.into(),
+                }.into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Compare,
+                    lhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Compare,
+                        lhs: ExpressionNode::Term(TermNode::Variable("x".into())).into(),
+                        rhs: ExpressionNode::Term(TermNode::Variable("y".into())).into(),
+                    }.into(),
+                    rhs: ExpressionNode::Term(TermNode::Boolean(true)).into(),
+                }.into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Minus,
+                    lhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Plus,
+                        lhs: ExpressionNode::Term(TermNode::Integer(1)).into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(2)).into(),
+                    }.into(),
+                    rhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Minus,
+                        lhs: ExpressionNode::Term(TermNode::Integer(3)).into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(4)).into(),
+                    }.into(),
+                }.into()
+            ).into(),
+        ];
+
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn test_expr_all_operators() {
+        let nodes = parse_block("
+            1 + 2 * 3 % 4;
+            x / 2 - 1;
+            1 < 2 == 3 >= 4;
+            1 <= 2 != 3 > 4;
+            x -% 1 +% 2 *% 3;
+        ");
+
+        let expected = vec![
+            BlockNode::Expression(
+                ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Plus,
+                    lhs: ExpressionNode::Term(TermNode::Integer(1)).into(),
+                    rhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Modulo,
+                        lhs: ExpressionNode::BinaryOperation {
+                            verb: BinaryVerb::Multiply,
+                            lhs: ExpressionNode::Term(TermNode::Integer(2)).into(),
+                            rhs: ExpressionNode::Term(TermNode::Integer(3)).into(),
+                        }.into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(4)).into(),
+                    }.into(),
+                }.into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Minus,
+                    lhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Divide,
+                        lhs: ExpressionNode::Term(TermNode::Variable("x".into())).into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(2)).into(),
+                    }.into(),
+                    rhs: ExpressionNode::Term(TermNode::Integer(1)).into(),
+                }.into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Compare,
+                    lhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Less,
+                        lhs: ExpressionNode::Term(TermNode::Integer(1)).into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(2)).into(),
+                    }.into(),
+                    rhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::GreaterEqual,
+                        lhs: ExpressionNode::Term(TermNode::Integer(3)).into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(4)).into(),
+                    }.into(),
+                }.into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::NotEqual,
+                    lhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::LessEqual,
+                        lhs: ExpressionNode::Term(TermNode::Integer(1)).into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(2)).into(),
+                    }.into(),
+                    rhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Greater,
+                        lhs: ExpressionNode::Term(TermNode::Integer(3)).into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(4)).into(),
+                    }.into(),
+                }.into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::WrappingPlus,
+                    lhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::WrappingMinus,
+                        lhs: ExpressionNode::Term(TermNode::Variable("x".into())).into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(1)).into(),
+                    }.into(),
+                    rhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::WrappingMultiply,
+                        lhs: ExpressionNode::Term(TermNode::Integer(2)).into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(3)).into(),
+                    }.into(),
+                }.into()
+            ).into(),
+        ];
+
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn test_expr_logic() {
+        let nodes = parse_block("
+            a || b && !c;
+            !!a == b || x < 1;
+        ");
+
+        let expected = vec![
+            BlockNode::Expression(
+                ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Or,
+                    lhs: ExpressionNode::Term(TermNode::Variable("a".into())).into(),
+                    rhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::And,
+                        lhs: ExpressionNode::Term(TermNode::Variable("b".into())).into(),
+                        rhs: ExpressionNode::Unary {
+                            op: UnaryVerb::Not,
+                            operand: ExpressionNode::Term(TermNode::Variable("c".into())).into(),
+                        }.into(),
+                    }.into(),
+                }.into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Or,
+                    lhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Compare,
+                        lhs: ExpressionNode::Unary {
+                            op: UnaryVerb::Not,
+                            operand: ExpressionNode::Unary {
+                                op: UnaryVerb::Not,
+                                operand: ExpressionNode::Term(TermNode::Variable("a".into())).into(),
+                            }.into(),
+                        }.into(),
+                        rhs: ExpressionNode::Term(TermNode::Variable("b".into())).into(),
+                    }.into(),
+                    rhs: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Less,
+                        lhs: ExpressionNode::Term(TermNode::Variable("x".into())).into(),
+                        rhs: ExpressionNode::Term(TermNode::Integer(1)).into(),
+                    }.into(),
+                }.into()
+            ).into(),
         ];
 
         assert_eq!(nodes, expected);
@@ -125,30 +504,95 @@ mod test {
         let nodes = parse_block("
             -5;
             -4 - -2;
+            x -1;
+            -(a + b);
         ");
 
         let expected = vec![
             BlockNode::Expression(
-                ExpressionNode::Term(
-                    TermNode::Integer(-5)
-                )
-            ),
+                ExpressionNode::Unary {
+                    op: UnaryVerb::Minus,
+                    operand: ExpressionNode::Term(
+                        TermNode::Integer(5)
+                    ).into(),
+                }.into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Minus,
+                    lhs: ExpressionNode::Unary {
+                        op: UnaryVerb::Minus,
+                        operand: ExpressionNode::Term(
+                            TermNode::Integer(4)
+                        ).into(),
+                    }.into(),
+                    rhs: ExpressionNode::Unary {
+                        op: UnaryVerb::Minus,
+                        operand: ExpressionNode::Term(
+                            TermNode::Integer(2)
+                        ).into(),
+                    }.into(),
+                }.into()
+            ).into(),
             BlockNode::Expression(
                 ExpressionNode::BinaryOperation {
                     verb: BinaryVerb::Minus,
                     lhs: ExpressionNode::Term(
-                        TermNode::Integer(-4)
+                        TermNode::Variable("x".into())
                     ).into(),
                     rhs: ExpressionNode::Term(
-                        TermNode::Integer(-2)
+                        TermNode::Integer(1)
                     ).into(),
-                }
-            ),
+                }.into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::Unary {
+                    op: UnaryVerb::Minus,
+                    operand: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Plus,
+                        lhs: ExpressionNode::Term(
+                            TermNode::Variable("a".into())
+                        ).into(),
+                        rhs: ExpressionNode::Term(
+                            TermNode::Variable("b".into())
+                        ).into(),
+                    }.into(),
+                }.into()
+            ).into(),
         ];
 
         assert_eq!(nodes, expected);
     }
 
+    #[test]
+    fn test_int_limits() {
+        let nodes = parse_block("
+            9223372036854775807;
+            -9223372036854775808;
+        ");
+
+        let expected = vec![
+            BlockNode::Expression(
+                ExpressionNode::Term(
+                    TermNode::Integer(i64::MAX)
+                ).into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::Term(
+                    TermNode::Integer(i64::MIN)
+                ).into()
+            ).into(),
+        ];
+
+        assert_eq!(nodes, expected);
+
+        // The errors point at the literal
+        let error = parse("function test() { 9223372036854775808; }").expect_err("Too large literals should be rejected");
+        assert_eq!(error, "Error at (1, 19): 9223372036854775808\n\tInteger literal 9223372036854775808 is out of range");
+        let error = parse("function test() {\n  -9223372036854775809; }").expect_err("Too small literals should be rejected");
+        assert_eq!(error, "Error at (2, 4): 9223372036854775809\n\tInteger literal 9223372036854775809 is out of range");
+    }
+
     #[test]
     fn test_string() {
         let nodes = parse_block("
@@ -159,8 +603,109 @@ mod test {
             BlockNode::Expression(
                 ExpressionNode::Term(
                     TermNode::String("This is a test".into())
-                )
-            ),
+                ).into()
+            ).into(),
+        ];
+
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let nodes = parse_block(r#"
+            "say \"hi\"\n\tand \\ \u{e9}\u{1F30D}";
+            r"C:\path\n";
+            r"";
+        "#);
+
+        let expected = vec![
+            BlockNode::Expression(
+                ExpressionNode::Term(
+                    TermNode::String("say \"hi\"\n\tand \\ é🌍".into())
+                ).into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::Term(
+                    TermNode::String("C:\\path\\n".into())
+                ).into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::Term(
+                    TermNode::String("".into())
+                ).into()
+            ).into(),
+        ];
+
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn test_invalid_escapes() {
+        let cases = [
+            (r#"return "a\qb";"#, "Error at (1, 28): \\q\n\tInvalid escape sequence \\q"),
+            (r#"return "\u{110000}";"#, "Error at (1, 27): \\u{110000}\n\tInvalid unicode escape \\u{110000}"),
+            (r#"return "\u{}";"#, "Error at (1, 27): \\u{}\n\tInvalid unicode escape \\u{}"),
+            (r#"return "\u{+1}";"#, "Error at (1, 27): \\u{+1}\n\tInvalid unicode escape \\u{+1}"),
+            (r#"return "\u41";"#, "Error at (1, 27): \\u\n\tUnicode escapes must be written as \\u{...}"),
+        ];
+
+        for (body, message) in cases {
+            let error = parse(&format!("function test() {{ {body} }}")).expect_err("The escape should be rejected");
+            assert_eq!(error, message, "{body}");
+        }
+
+        // All invalid escapes are reported
+        let error = parse(r#"function test() { "\a\b"; }"#).expect_err("The escapes should be rejected");
+        assert!(error.contains("(1, 20)") && error.contains("(1, 22)"), "{error}");
+
+        parse(r#"function test() { "\"; }"#).expect_err("The string should be unterminated");
+    }
+
+    #[test]
+    fn test_call() {
+        let nodes = parse_block("
+            five();
+            double(x, 2 + 3,);
+            double(five());
+        ");
+
+        let expected = vec![
+            BlockNode::Expression(
+                ExpressionNode::Call {
+                    name: "five".into(),
+                    args: vec![],
+                }.into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::Call {
+                    name: "double".into(),
+                    args: vec![
+                        ExpressionNode::Term(
+                            TermNode::Variable("x".into())
+                        ).into(),
+                        ExpressionNode::BinaryOperation {
+                            verb: BinaryVerb::Plus,
+                            lhs: ExpressionNode::Term(
+                                TermNode::Integer(2)
+                            ).into(),
+                            rhs: ExpressionNode::Term(
+                                TermNode::Integer(3)
+                            ).into(),
+                        }.into(),
+                    ],
+                }.into()
+            ).into(),
+            BlockNode::Expression(
+                ExpressionNode::Call {
+                    name: "double".into(),
+                    args: vec![
+                        ExpressionNode::Call {
+                            name: "five".into(),
+                            args: vec![],
+                        }.into(),
+                    ],
+                }.into()
+            ).into(),
         ];
 
         assert_eq!(nodes, expected);
@@ -177,13 +722,13 @@ mod test {
             BlockNode::Expression(
                 ExpressionNode::Term(
                     TermNode::Boolean(true)
-                )
-            ),
+                ).into()
+            ).into(),
             BlockNode::Expression(
                 ExpressionNode::Term(
                     TermNode::Boolean(false)
-                )
-            ),
+                ).into()
+            ).into(),
         ];
 
         assert_eq!(nodes, expected);
@@ -1,5 +1,5 @@
-use pest::iterators::Pair;
-use super::ast::{FunctionNode, FunctionParam};
+use pest::iterators::{Pair, Pairs};
+use super::ast::{FunctionNode, FunctionParam, Span};
 use super::error::ParseError;
 use super::from_pest::{FromPest, ParsePest};
 use super::utils::{parse_next, parse_next_option};
@@ -10,12 +10,15 @@ impl FromPest<'_> for FunctionNode {
         match pair.as_rule() {
             Rule::func => {
                 let mut inner = pair.clone().into_inner();
+                let doc = parse_doc(&mut inner);
 
                 Ok(FunctionNode {
                     name: parse_next(&mut inner, &pair)?,
                     parameters: parse_next(&mut inner, &pair)?,
                     return_type: parse_next_option(&mut inner)?,
                     block: parse_next(&mut inner, &pair)?,
+                    doc,
+                    span: Span::from(&pair),
                 })
             }
             rule => Err(ParseError::wrong_rule(&pair, rule))
@@ -23,11 +26,30 @@ impl FromPest<'_> for FunctionNode {
     }
 }
 
+/// Join the leading doc comment lines, without the `///` and the first space.
+fn parse_doc(inner: &mut Pairs<'_, Rule>) -> Option<String> {
+    let mut lines = vec![];
+
+    while let Some(line) = inner.peek().filter(|p| p.as_rule() == Rule::doc_comment) {
+        inner.next();
+
+        let text = line.into_inner().as_str();
+        lines.push(text.strip_prefix(' ').unwrap_or(text).trim_end().to_string());
+    }
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
 impl FromPest<'_> for FunctionParam {
     fn from_pest(pair: Pair<'_, Rule>) -> Result<Self, ParseError> {
+        let span = Span::from(&pair);
         let (ident, type_name) = pair.parse()?;
 
-        Ok(FunctionParam { name: ident, type_name })
+        Ok(FunctionParam { name: ident, type_name, span })
     }
 }
 
@@ -40,22 +62,24 @@ mod test {
 
     #[test]
     fn test_function_simple() {
-        let ast = parse_helper("function test() {}");
+        let ast = parse_helper("function test() {}").without_spans();
 
         let expected = FunctionNode {
             name: "test".into(),
             parameters: vec![],
             return_type: None,
             block: vec![],
+            doc: None,
+            span: Span::default(),
         };
 
         assert_eq!(ast.nodes.len(), 1);
-        assert_eq!(ast.nodes[0], expected);
+        assert_eq!(*ast.nodes[0], expected);
     }
 
     #[test]
     fn test_function_params() {
-        let ast = parse_helper("function test(x: int, y: string) {}");
+        let ast = parse_helper("function test(x: int, y: string) {}").without_spans();
 
         let expected = FunctionNode {
             name: "test".into(),
@@ -63,38 +87,44 @@ mod test {
                 FunctionParam {
                     name: "x".into(),
                     type_name: Type::Int,
+                    span: Span::default(),
                 },
                 FunctionParam {
                     name: "y".into(),
                     type_name: Type::String,
+                    span: Span::default(),
                 }
             ],
             return_type: None,
             block: vec![],
+            doc: None,
+            span: Span::default(),
         };
 
         assert_eq!(ast.nodes.len(), 1);
-        assert_eq!(ast.nodes[0], expected);
+        assert_eq!(*ast.nodes[0], expected);
     }
 
     #[test]
     fn test_function_return_type() {
-        let ast = parse_helper("function test() -> string {}");
+        let ast = parse_helper("function test() -> string {}").without_spans();
 
         let expected = FunctionNode {
             name: "test".into(),
             parameters: vec![],
             return_type: Some(Type::String),
             block: vec![],
+            doc: None,
+            span: Span::default(),
         };
 
         assert_eq!(ast.nodes.len(), 1);
-        assert_eq!(ast.nodes[0], expected);
+        assert_eq!(*ast.nodes[0], expected);
     }
 
     #[test]
     fn test_function_return_type_with_block() {
-        let ast = parse_helper("function test() -> string { return 5; }");
+        let ast = parse_helper("function test() -> string { return 5; }").without_spans();
 
         let expected = FunctionNode {
             name: "test".into(),
@@ -102,12 +132,35 @@ mod test {
             return_type: Some(Type::String),
             block: vec![
                 BlockNode::Return(
-                    ExpressionNode::Term(TermNode::Integer(5))
-                )
+                    ExpressionNode::Term(TermNode::Integer(5)).into()
+                ).into()
             ],
+            doc: None,
+            span: Span::default(),
         };
 
         assert_eq!(ast.nodes.len(), 1);
-        assert_eq!(ast.nodes[0], expected);
+        assert_eq!(*ast.nodes[0], expected);
+    }
+
+    #[test]
+    fn test_doc_comment() {
+        let ast = parse_helper("
+            // Not documentation
+            /// Returns five.
+            ///
+            ///     return 5;
+            function five() -> int { return 5; }
+
+            //// Not documentation either
+            function test() {
+                /// Not attached to a function
+                five();
+            }
+        ");
+
+        assert_eq!(ast.nodes.len(), 2);
+        assert_eq!(ast.nodes[0].doc, Some("Returns five.\n\n    return 5;".into()));
+        assert_eq!(ast.nodes[1].doc, None);
     }
 }
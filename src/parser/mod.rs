@@ -8,13 +8,15 @@ mod from_pest;
 mod program;
 mod error;
 mod utils;
+mod nesting;
 
 #[cfg(test)]
-mod test;
+pub(crate) mod test;
 
 use pest::Parser;
 use pest_derive::Parser;
 use self::ast::Program;
+pub use self::nesting::check_nesting;
 
 #[derive(Parser)]
 #[grammar = "kwlang.pest"]
@@ -0,0 +1,234 @@
+use super::ast::Span;
+
+/// Check how deeply a source is nested before parsing it, and return the
+/// depth of its deepest point.
+///
+/// The parser and every pass after it recurse for each level, so this bounds
+/// the stack they need. Each open bracket is a level, and so is each operator
+/// of the expression and each `else` of the if-statement inside it. That is at
+/// least as deep as the syntax tree. The check itself doesn't recurse, and
+/// returns the location where the depth first exceeds `max`.
+pub fn check_nesting(input: &str, max: usize) -> Result<usize, Span> {
+    let mut scanner = Scanner {
+        input,
+        offset: 0,
+        line: 1,
+        column: 1,
+    };
+    // The levels of the open brackets, after the top level
+    let mut levels = vec![Level::default()];
+    let mut depth = 0;
+    let mut deepest = 0;
+    let mut after_block = false;
+
+    while let Some((token, span)) = scanner.next_token() {
+        if after_block && token != Token::Else {
+            let level = levels.last_mut().expect("The top level is never closed");
+            depth -= level.operators + level.branches;
+            *level = Level::default();
+        }
+
+        after_block = false;
+
+        match token {
+            Token::Open => {
+                levels.push(Level::default());
+                depth += 1;
+            }
+            Token::Close { block } => {
+                if levels.len() > 1 {
+                    let level = levels.pop().expect("The level was just checked");
+                    depth -= 1 + level.operators + level.branches;
+                }
+
+                after_block = block;
+            }
+            Token::Separator => {
+                let level = levels.last_mut().expect("The top level is never closed");
+                depth -= level.operators + level.branches;
+                *level = Level::default();
+            }
+            Token::Operator => {
+                levels.last_mut().expect("The top level is never closed").operators += 1;
+                depth += 1;
+            }
+            Token::Else => {
+                levels.last_mut().expect("The top level is never closed").branches += 1;
+                depth += 1;
+            }
+            Token::Other => {}
+        }
+
+        if depth > max {
+            return Err(span);
+        }
+
+        deepest = deepest.max(depth);
+    }
+
+    Ok(deepest)
+}
+
+#[derive(Debug, Default)]
+struct Level {
+    /// The operators of the expression so far.
+    operators: usize,
+    /// The `else` branches of the if-statement so far.
+    branches: usize,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Token {
+    Open,
+    Close { block: bool },
+    /// The end of a statement or an argument.
+    Separator,
+    Operator,
+    Else,
+    Other,
+}
+
+struct Scanner<'a> {
+    input: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.offset..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+
+        Some(c)
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        let found = self.input[self.offset..].starts_with(text);
+
+        if found {
+            text.chars().for_each(|_| { self.bump(); });
+        }
+
+        found
+    }
+
+    /// Skip whitespace, comments and literals, and return the next token that can change the depth.
+    fn next_token(&mut self) -> Option<(Token, Span)> {
+        loop {
+            let (start, line, column) = (self.offset, self.line, self.column);
+            let c = self.peek()?;
+
+            let token = if c.is_whitespace() {
+                self.bump();
+                continue;
+            } else if self.eat("//") {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.bump();
+                }
+                continue;
+            } else if self.eat("/*") {
+                self.skip_block_comment();
+                continue;
+            } else if self.eat("r\"") {
+                while self.bump().is_some_and(|c| c != '"') {}
+                Token::Other
+            } else if self.eat("\"") {
+                while let Some(c) = self.bump() {
+                    match c {
+                        '\\' => { self.bump(); }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+                Token::Other
+            } else if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+                while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+                    self.bump();
+                }
+
+                if &self.input[start..self.offset] == "else" { Token::Else } else { Token::Other }
+            } else if self.eat("->") {
+                Token::Other
+            } else if ["+%", "-%", "*%", "==", "!=", "<=", ">=", "&&", "||"].into_iter().any(|operator| self.eat(operator)) {
+                Token::Operator
+            } else {
+                self.bump();
+
+                match c {
+                    '(' | '{' => Token::Open,
+                    ')' => Token::Close { block: false },
+                    '}' => Token::Close { block: true },
+                    ';' | ',' => Token::Separator,
+                    '+' | '-' | '*' | '/' | '%' | '!' | '<' | '>' => Token::Operator,
+                    _ => Token::Other,
+                }
+            };
+
+            return Some((token, Span { start, end: self.offset, line, column }));
+        }
+    }
+
+    fn skip_block_comment(&mut self) {
+        let mut open = 1;
+
+        while open > 0 && self.peek().is_some() {
+            if self.eat("/*") {
+                open += 1;
+            } else if self.eat("*/") {
+                open -= 1;
+            } else {
+                self.bump();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::check_nesting;
+
+    fn depth(input: &str) -> usize {
+        check_nesting(input, usize::MAX).expect("The depth is not limited")
+    }
+
+    #[test]
+    fn test_depth() {
+        assert_eq!(depth(""), 0);
+        assert_eq!(depth("function main() -> int { return 0; }"), 1);
+        assert_eq!(depth("function main() -> int { return ((1)); }"), 3);
+        assert_eq!(depth("function main() -> int { return 1 + 2 * -3; }"), 4);
+        assert_eq!(depth("function main() { if (true) { if (true) {} } }"), 3);
+
+        // Statements and arguments end the expressions before them
+        assert_eq!(depth("function main() { f(1 + 1, 2 + 2); x = 1 + 1; y = 2 + 2; }"), 3);
+
+        // The branches of an if-statement are nested, but separate if-statements aren't
+        assert_eq!(depth("function main() { if (a) {} else if (b) {} else if (c) {} else {} }"), 5);
+        assert_eq!(depth("function main() { if (a) {} else {} if (b) {} else {} }"), 3);
+
+        // Brackets, operators and keywords in literals and comments don't count
+        assert_eq!(depth(r#"function main() { "((\" + else"; r"((\"; /* (( /* + */ - */ // ((( }"#), 1);
+    }
+
+    #[test]
+    fn test_limit() {
+        let input = "function main() -> int {\n    return ((1));\n}";
+
+        assert_eq!(check_nesting(input, 3), Ok(3));
+
+        let span = check_nesting(input, 2).expect_err("The source is too deep");
+        assert_eq!((span.line, span.column), (2, 13));
+    }
+}
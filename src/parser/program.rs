@@ -1,3 +1,4 @@
+use std::sync::Arc;
 use pest::iterators::Pairs;
 use super::ast::Program;
 use super::error::ParseError;
@@ -14,7 +15,7 @@ impl TryFrom<&mut Pairs<'_, Rule>> for Program {
                 nodes: ParseError::merge(
                     value.filter_map(|p| match p.as_rule() {
                         Rule::EOI => None,
-                        _ => Some(p.parse())
+                        _ => Some(p.parse().map(Arc::new))
                     })
                 )?
             }
@@ -25,6 +26,8 @@ impl TryFrom<&mut Pairs<'_, Rule>> for Program {
 #[cfg(test)]
 mod test {
     use super::super::{
+        ast::*,
+        parse,
         test::helper::*,
     };
 
@@ -35,4 +38,84 @@ mod test {
         assert_eq!(program.nodes.len(), 0);
     }
 
+    #[test]
+    fn test_comments() {
+        let program = parse_helper("
+            // A line comment
+            function main() -> int { // After code
+                /* A block comment */
+                var x: int = 1 /* between tokens */ + 2;
+                /* Block comments /* can be */ nested */
+                return x; // return 3;
+            }
+            /*
+             * function test() {}
+             */
+        ").without_spans();
+
+        let expected = vec![
+            BlockNode::VariableDefinition {
+                name: "x".into(),
+                type_name: Type::Int,
+                value: ExpressionNode::BinaryOperation {
+                    verb: BinaryVerb::Plus,
+                    lhs: ExpressionNode::Term(TermNode::Integer(1)).into(),
+                    rhs: ExpressionNode::Term(TermNode::Integer(2)).into(),
+                }.into(),
+            }.into(),
+            BlockNode::Return(
+                ExpressionNode::Term(TermNode::Variable("x".into())).into()
+            ).into(),
+        ];
+
+        assert_eq!(program.nodes.len(), 1);
+        assert_eq!(program.nodes[0].block, expected);
+
+        parse("function main() { /* /* */ }").expect_err("Nested block comments must be closed");
+    }
+
+    #[test]
+    fn test_comment_in_string() {
+        let program = parse_helper("
+            function main() -> string {
+                return \"// /* not comments */\";
+            }
+        ").without_spans();
+
+        assert_eq!(
+            program.nodes[0].block,
+            vec![
+                BlockNode::Return(
+                    ExpressionNode::Term(TermNode::String("// /* not comments */".into())).into()
+                ).into(),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        let input = "function main() -> int {\n    return 1 + (2 - x);\n}\n";
+        let program = parse_helper(input);
+
+        let function = &program.nodes[0];
+        assert_eq!((function.span.line, function.span.column), (1, 1));
+
+        let statement = &function.block[0];
+        assert_eq!((statement.span.line, statement.span.column), (2, 5));
+        assert_eq!(&input[statement.span.start..statement.span.end], "return 1 + (2 - x)");
+
+        if let BlockNode::Return(expr) = &statement.node {
+            assert_eq!((expr.span.line, expr.span.column), (2, 12));
+            assert_eq!(&input[expr.span.start..expr.span.end], "1 + (2 - x)");
+
+            if let ExpressionNode::BinaryOperation { rhs, .. } = &expr.node {
+                assert_eq!(&input[rhs.span.start..rhs.span.end], "2 - x");
+                assert_eq!((rhs.span.line, rhs.span.column), (2, 17));
+            } else {
+                panic!("Expected a binary operation, got {expr:?}");
+            }
+        } else {
+            panic!("Expected a return statement, got {statement:?}");
+        }
+    }
 }
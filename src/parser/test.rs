@@ -1,4 +1,5 @@
 pub mod helper {
+    use std::sync::Arc;
     use super::super::{
         parse,
         ast::*,
@@ -13,11 +14,11 @@ pub mod helper {
         })
     }
 
-    /// Parse a function body to a list of block nodes.
+    /// Parse a function body to a list of block nodes, without their spans.
     ///
     /// Reduces the boilerplate of tests handing the function when they
     /// only care about the statements.
-    pub fn parse_block(input: &str) -> Vec<BlockNode> {
+    pub fn parse_block(input: &str) -> Vec<Statement> {
         let mut nodes = parse_helper(
             &format!("
                 function test() {{
@@ -28,10 +29,106 @@ pub mod helper {
 
         assert_eq!(nodes.len(), 1);
 
-        if let Some(FunctionNode {block, .. }) = nodes.pop() {
-            block
+        if let Some(function) = nodes.pop() {
+            function.block.clone().without_spans()
         } else {
             panic!()
         }
     }
+
+    /// Clear the spans of a node and its children, so that it can be
+    /// compared with a node built in a test.
+    pub trait WithoutSpans {
+        fn without_spans(self) -> Self;
+    }
+
+    impl<T: WithoutSpans> WithoutSpans for Vec<T> {
+        fn without_spans(self) -> Self {
+            self.into_iter().map(WithoutSpans::without_spans).collect()
+        }
+    }
+
+    impl<T: WithoutSpans> WithoutSpans for Option<T> {
+        fn without_spans(self) -> Self {
+            self.map(WithoutSpans::without_spans)
+        }
+    }
+
+    impl<T: WithoutSpans + Clone> WithoutSpans for Arc<T> {
+        fn without_spans(self) -> Self {
+            Arc::new(Arc::unwrap_or_clone(self).without_spans())
+        }
+    }
+
+    impl<T: WithoutSpans> WithoutSpans for Box<T> {
+        fn without_spans(self) -> Self {
+            Box::new((*self).without_spans())
+        }
+    }
+
+    impl WithoutSpans for Program {
+        fn without_spans(self) -> Self {
+            Program { nodes: self.nodes.without_spans() }
+        }
+    }
+
+    impl WithoutSpans for FunctionNode {
+        fn without_spans(self) -> Self {
+            FunctionNode {
+                parameters: self.parameters.without_spans(),
+                block: self.block.without_spans(),
+                span: Span::default(),
+                ..self
+            }
+        }
+    }
+
+    impl WithoutSpans for FunctionParam {
+        fn without_spans(self) -> Self {
+            FunctionParam { span: Span::default(), ..self }
+        }
+    }
+
+    impl WithoutSpans for Statement {
+        fn without_spans(self) -> Self {
+            let node = match self.node {
+                BlockNode::VariableDefinition { name, type_name, value } => {
+                    BlockNode::VariableDefinition { name, type_name, value: value.without_spans() }
+                }
+                BlockNode::Assignment { lhs, rhs } => BlockNode::Assignment { lhs, rhs: rhs.without_spans() },
+                BlockNode::Expression(expr) => BlockNode::Expression(expr.without_spans()),
+                BlockNode::Block(block) => BlockNode::Block(block.without_spans()),
+                BlockNode::IfStatement { condition, block, else_block } => BlockNode::IfStatement {
+                    condition: condition.without_spans(),
+                    block: block.without_spans(),
+                    else_block: else_block.without_spans(),
+                },
+                BlockNode::WhileLoop { condition, block } => BlockNode::WhileLoop {
+                    condition: condition.without_spans(),
+                    block: block.without_spans(),
+                },
+                BlockNode::Return(expr) => BlockNode::Return(expr.without_spans()),
+                node @ (BlockNode::Break | BlockNode::Continue) => node,
+            };
+
+            node.into()
+        }
+    }
+
+    impl WithoutSpans for Expression {
+        fn without_spans(self) -> Self {
+            let node = match self.node {
+                ExpressionNode::BinaryOperation { verb, lhs, rhs } => ExpressionNode::BinaryOperation {
+                    verb,
+                    lhs: lhs.without_spans(),
+                    rhs: rhs.without_spans(),
+                },
+                ExpressionNode::Unary { op, operand } => ExpressionNode::Unary { op, operand: operand.without_spans() },
+                ExpressionNode::Call { name, args } => ExpressionNode::Call { name, args: args.without_spans() },
+                ExpressionNode::Term(term) => ExpressionNode::Term(term),
+            };
+
+            node.into()
+        }
+    }
 }
@@ -0,0 +1,66 @@
+use std::fmt::{Display, Formatter};
+use crate::parser::ast::{Ident, Span};
+
+/// What is wrong with the names in a program.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ErrorKind {
+    UndefinedVariable(Ident),
+    UndefinedFunction(Ident),
+    DuplicateFunction {
+        name: Ident,
+        previous: Span,
+    },
+    /// A function with the same name as a builtin.
+    RedefinedBuiltin(Ident),
+    /// A function with the same name as a native function.
+    RedefinedNative(Ident),
+    DuplicateParameter {
+        name: Ident,
+        previous: Span,
+    },
+    /// A variable defined when another variable with the same name is visible.
+    Shadowing {
+        name: Ident,
+        previous: Span,
+    },
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UndefinedVariable(name) => write!(f, "Undefined variable {name}"),
+            Self::UndefinedFunction(name) => write!(f, "Undefined function {name}"),
+            Self::DuplicateFunction { name, previous } => {
+                write!(f, "Function {name} is already defined at {previous}")
+            }
+            Self::RedefinedBuiltin(name) => write!(f, "Function {name} is a builtin, and can't be redefined"),
+            Self::RedefinedNative(name) => write!(f, "Function {name} is a native function, and can't be redefined"),
+            Self::DuplicateParameter { name, previous } => {
+                write!(f, "Parameter {name} is already defined at {previous}")
+            }
+            Self::Shadowing { name, previous } => {
+                write!(f, "Definition of {name} shadows the variable defined at {previous}")
+            }
+        }
+    }
+}
+
+/// A name resolution error, with the node it was found in.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ResolveError {
+    pub kind: ErrorKind,
+    pub span: Span,
+}
+
+impl ResolveError {
+    /// Format the error with the location prefixed by the file name.
+    pub fn report(&self, file: &str) -> String {
+        format!("{file}:{}: error: {}", self.span, self.kind)
+    }
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.span, self.kind)
+    }
+}
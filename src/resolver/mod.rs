@@ -0,0 +1,190 @@
+mod error;
+
+#[cfg(test)]
+mod test;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use crate::builtin::Builtin;
+use crate::parser::ast::{BlockNode, Expression, ExpressionNode, FunctionNode, Ident, Program, Signature, Span, Statement, TermNode, Variable};
+pub use self::error::{ErrorKind, ResolveError};
+
+/// Link every variable in the program to its definition, and report
+/// undefined, duplicate and shadowed names.
+///
+/// Each variable is given a slot in the frame of its function, see [`Variable`].
+/// The program can call the string functions, see [`crate::strings::natives`].
+#[cfg(test)]
+pub fn resolve(program: &mut Program) -> Result<(), Vec<ResolveError>> {
+    resolve_with_natives(program, &crate::strings::signatures())
+}
+
+/// Resolve a program that can also call the given native functions.
+pub fn resolve_with_natives(program: &mut Program, natives: &[Signature]) -> Result<(), Vec<ResolveError>> {
+    let mut resolver = Resolver {
+        functions: HashMap::new(),
+        natives: natives.iter().map(|native| native.name.clone()).collect(),
+        layers: vec![],
+        slots: 0,
+        errors: vec![],
+    };
+
+    for function in &program.nodes {
+        if Builtin::from_name(&function.name).is_some() {
+            resolver.error(ErrorKind::RedefinedBuiltin(function.name.clone()), function.span);
+        } else if resolver.natives.contains(&function.name) {
+            resolver.error(ErrorKind::RedefinedNative(function.name.clone()), function.span);
+        } else if let Some(previous) = resolver.functions.insert(function.name.clone(), function.span) {
+            resolver.error(ErrorKind::DuplicateFunction { name: function.name.clone(), previous }, function.span);
+        }
+    }
+
+    for function in &mut program.nodes {
+        // Only copies functions that are shared, such as those of sources loaded before
+        resolver.resolve_function(Arc::make_mut(function));
+    }
+
+    if resolver.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(resolver.errors)
+    }
+}
+
+struct Resolver {
+    /// Where each function is defined.
+    functions: HashMap<Ident, Span>,
+    /// The names of the native functions.
+    natives: HashSet<Ident>,
+    /// The slots and definitions of the variables in the function being resolved,
+    /// innermost block last.
+    layers: Vec<HashMap<Ident, (usize, Span)>>,
+    /// The number of slots used in the function being resolved.
+    slots: usize,
+    errors: Vec<ResolveError>,
+}
+
+impl Resolver {
+    fn error(&mut self, kind: ErrorKind, span: Span) {
+        self.errors.push(ResolveError { kind, span });
+    }
+
+    fn subscope(&mut self, f: impl FnOnce(&mut Self)) {
+        self.layers.push(HashMap::new());
+        f(self);
+        self.layers.pop();
+    }
+
+    fn lookup(&self, name: &str) -> Option<(usize, Span)> {
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|layer| layer.get(name).copied())
+    }
+
+    /// Give a new variable the next slot.
+    fn define(&mut self, name: &str, span: Span) -> usize {
+        let slot = self.slots;
+        self.slots += 1;
+
+        self.layers
+            .last_mut()
+            .expect("Variable defined outside a function")
+            .insert(name.to_string(), (slot, span));
+
+        slot
+    }
+
+    fn resolve_function(&mut self, function: &mut FunctionNode) {
+        self.slots = 0;
+
+        self.subscope(|resolver| {
+            for param in &function.parameters {
+                if let Some((_, previous)) = resolver.lookup(&param.name) {
+                    resolver.error(ErrorKind::DuplicateParameter { name: param.name.clone(), previous }, param.span);
+                }
+
+                // Duplicate parameters still get a slot, so that the slots match the parameter list
+                resolver.define(&param.name, param.span);
+            }
+
+            resolver.resolve_block(&mut function.block);
+        });
+    }
+
+    fn resolve_block(&mut self, block: &mut [Statement]) {
+        for node in block {
+            self.resolve_statement(node);
+        }
+    }
+
+    fn resolve_statement(&mut self, node: &mut Statement) {
+        let span = node.span;
+
+        match &mut node.node {
+            BlockNode::VariableDefinition { name, value, .. } => {
+                // The value can't refer to the variable being defined
+                self.resolve_expression(value);
+
+                if let Some((_, previous)) = self.lookup(&name.name) {
+                    self.error(ErrorKind::Shadowing { name: name.name.clone(), previous }, span);
+                }
+
+                name.slot = Some(self.define(&name.name, span));
+            }
+            BlockNode::Assignment { lhs, rhs } => {
+                self.resolve_variable(lhs, span);
+                self.resolve_expression(rhs);
+            }
+            BlockNode::Expression(expr) | BlockNode::Return(expr) => self.resolve_expression(expr),
+            BlockNode::Block(nested) => self.subscope(|resolver| resolver.resolve_block(nested)),
+            BlockNode::IfStatement { condition, block, else_block } => {
+                self.resolve_expression(condition);
+                self.subscope(|resolver| resolver.resolve_block(block));
+
+                if let Some(else_block) = else_block {
+                    self.subscope(|resolver| resolver.resolve_block(else_block));
+                }
+            }
+            BlockNode::WhileLoop { condition, block } => {
+                self.resolve_expression(condition);
+                self.subscope(|resolver| resolver.resolve_block(block));
+            }
+            BlockNode::Break | BlockNode::Continue => {}
+        }
+    }
+
+    fn resolve_expression(&mut self, expr: &mut Expression) {
+        let span = expr.span;
+
+        match &mut expr.node {
+            ExpressionNode::BinaryOperation { lhs, rhs, .. } => {
+                self.resolve_expression(lhs);
+                self.resolve_expression(rhs);
+            }
+            ExpressionNode::Unary { operand, .. } => self.resolve_expression(operand),
+            ExpressionNode::Call { name, args } => {
+                let defined = self.functions.contains_key(name)
+                    || self.natives.contains(name)
+                    || Builtin::from_name(name).is_some();
+
+                if !defined {
+                    self.error(ErrorKind::UndefinedFunction(name.clone()), span);
+                }
+
+                for arg in args {
+                    self.resolve_expression(arg);
+                }
+            }
+            ExpressionNode::Term(TermNode::Variable(variable)) => self.resolve_variable(variable, span),
+            ExpressionNode::Term(_) => {}
+        }
+    }
+
+    fn resolve_variable(&mut self, variable: &mut Variable, span: Span) {
+        match self.lookup(&variable.name) {
+            Some((slot, _)) => variable.slot = Some(slot),
+            None => self.error(ErrorKind::UndefinedVariable(variable.name.clone()), span),
+        }
+    }
+}
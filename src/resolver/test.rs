@@ -0,0 +1,183 @@
+use crate::parser::ast::{BlockNode, ExpressionNode, Program, TermNode, Type, Variable};
+use crate::parser::parse;
+use crate::parser::test::helper::WithoutSpans;
+use super::{resolve, ErrorKind};
+
+/// Parse and resolve a program, and return the kinds and lines of the errors.
+fn errors(source: &str) -> Vec<(ErrorKind, usize)> {
+    let mut program = parse(source).expect("Could not parse program");
+
+    resolve(&mut program)
+        .err()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|error| (error.kind, error.span.line))
+        .collect()
+}
+
+#[test]
+fn test_example_programs() {
+    for source in [
+        include_str!("../../tests/arithmetic.kw"),
+        include_str!("../../tests/expression.kw"),
+        include_str!("../../tests/function_call.kw"),
+        include_str!("../../tests/if_else.kw"),
+        include_str!("../../tests/if_statement.kw"),
+        include_str!("../../tests/logic.kw"),
+        include_str!("../../tests/simple_return.kw"),
+        include_str!("../../tests/variable_definition.kw"),
+        include_str!("../../tests/while_loop.kw"),
+    ] {
+        assert_eq!(errors(source), vec![]);
+    }
+}
+
+#[test]
+fn test_slots() {
+    let mut program: Program = parse("
+        function main(a: int) -> int {
+            if (true) {
+                var x: int = a;
+            } else {
+                var x: int = 2;
+                x = a;
+            }
+
+            var y: int = 3;
+            return y;
+        }
+    ").expect("Could not parse program");
+
+    resolve(&mut program).expect("Could not resolve program");
+
+    let block = program.nodes[0].block.clone().without_spans();
+
+    let BlockNode::IfStatement { block: if_block, else_block: Some(else_block), .. } = &block[0].node else {
+        panic!("Expected an if-statement, got {:?}", block[0]);
+    };
+
+    assert_eq!(
+        if_block[0].node,
+        BlockNode::VariableDefinition {
+            name: Variable { name: "x".into(), slot: Some(1) },
+            type_name: Type::Int,
+            value: ExpressionNode::Term(TermNode::Variable(Variable { name: "a".into(), slot: Some(0) })).into(),
+        },
+    );
+
+    // Variables with the same name in different blocks are different variables
+    assert_eq!(
+        else_block[1].node,
+        BlockNode::Assignment {
+            lhs: Variable { name: "x".into(), slot: Some(2) },
+            rhs: ExpressionNode::Term(TermNode::Variable(Variable { name: "a".into(), slot: Some(0) })).into(),
+        },
+    );
+
+    assert_eq!(
+        block[2].node,
+        BlockNode::Return(ExpressionNode::Term(TermNode::Variable(Variable { name: "y".into(), slot: Some(3) })).into()),
+    );
+}
+
+#[test]
+fn test_duplicates() {
+    let errors = errors("
+        function main() -> int {
+            return 0;
+        }
+
+        function add(x: int, x: int) -> int {
+            return x;
+        }
+
+        function main() -> int {
+            return 1;
+        }
+    ");
+
+    assert_eq!(
+        errors.iter().map(|(kind, line)| (kind.to_string(), *line)).collect::<Vec<_>>(),
+        vec![
+            ("Function main is already defined at 2:9".to_string(), 10),
+            ("Parameter x is already defined at 6:22".to_string(), 6),
+        ],
+    );
+}
+
+#[test]
+fn test_builtins() {
+    let errors = errors("
+        function main() -> int {
+            println(1);
+            return 0;
+        }
+
+        function print(x: int) {
+        }
+    ");
+
+    assert_eq!(errors, vec![(ErrorKind::RedefinedBuiltin("print".into()), 7)]);
+}
+
+#[test]
+fn test_string_functions() {
+    // The string functions are natives
+    let errors = errors("
+        function len(s: string) -> int {
+            return 0;
+        }
+    ");
+
+    assert_eq!(errors, vec![(ErrorKind::RedefinedNative("len".into()), 2)]);
+}
+
+#[test]
+fn test_undefined_names() {
+    let errors = errors("
+        function main() -> int {
+            var x: int = x;
+            y = 1;
+
+            if (true) {
+                var z: int = 0;
+            }
+
+            return missing(z);
+        }
+    ");
+
+    assert_eq!(
+        errors,
+        vec![
+            (ErrorKind::UndefinedVariable("x".into()), 3),
+            (ErrorKind::UndefinedVariable("y".into()), 4),
+            (ErrorKind::UndefinedFunction("missing".into()), 10),
+            (ErrorKind::UndefinedVariable("z".into()), 10),
+        ],
+    );
+}
+
+#[test]
+fn test_shadowing() {
+    let errors = errors("
+        function main(x: int) -> int {
+            var x: int = 1;
+            var y: int = 2;
+
+            while (true) {
+                var y: int = 3;
+            }
+
+            return 0;
+        }
+    ");
+
+    assert_eq!(
+        errors.iter().map(|(kind, line)| (kind.to_string(), *line)).collect::<Vec<_>>(),
+        vec![
+            ("Definition of x shadows the variable defined at 2:23".to_string(), 3),
+            ("Definition of y shadows the variable defined at 4:13".to_string(), 7),
+        ],
+    );
+}
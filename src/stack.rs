@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::io;
+use crate::parser::ast::Program;
+
+/// The most stack the parser, the analysis or the compilers use for a level
+/// of nesting in the source, see [`crate::parser::check_nesting`].
+///
+/// The tests of the interpreter run every pass on sources nested to the
+/// default limit, on a stack of this size.
+pub const PASS_STACK_PER_LEVEL: usize = if cfg!(debug_assertions) { 32 * 1024 } else { 4 * 1024 };
+
+/// The most stack the tree-walker uses for a level of nesting in a function,
+/// see [`crate::parser::ast::FunctionNode::depth`]. Each call adds a level.
+///
+/// The tests of the interpreter run functions nested to the default limit,
+/// and recursion to the default call depth, on a stack of this size.
+pub const RUN_STACK_PER_LEVEL: usize = if cfg!(debug_assertions) { 24 * 1024 } else { 4 * 1024 };
+
+/// The most levels of nesting the tree-walker reaches when calling any
+/// function of the program, with at most `max_call_depth` calls running.
+///
+/// Each running call but the innermost is stopped at a call, so it adds the
+/// depth of that call and a level for the call itself. The bound follows the
+/// calls between the functions, and once they have gone through every
+/// function there is a cycle, so each further call adds the deepest call.
+pub fn call_levels(program: &Program, max_call_depth: usize) -> usize {
+    let indices: HashMap<_, _> = program.nodes.iter()
+        .enumerate()
+        .map(|(index, function)| (function.name.as_str(), index))
+        .collect();
+    let depths: Vec<_> = program.nodes.iter().map(|function| function.depth()).collect();
+    // The calls of each function, to functions of the program or others
+    let calls: Vec<Vec<_>> = program.nodes.iter()
+        .map(|function| function.calls().into_iter().map(|(name, depth)| (indices.get(name).copied(), depth)).collect())
+        .collect();
+    let deepest_call = calls.iter().flatten().map(|(_, depth)| depth + 1).max().unwrap_or(0);
+
+    // The most levels of a call to each function, with `running` calls
+    let mut levels = depths.clone();
+
+    for running in 2..=max_call_depth {
+        if running > program.nodes.len() + 1 {
+            let remaining = max_call_depth - running + 1;
+            return levels.into_iter().max().unwrap_or(0).saturating_add(remaining.saturating_mul(deepest_call));
+        }
+
+        let next: Vec<_> = calls.iter()
+            .zip(&depths)
+            .map(|(calls, &depth)| calls.iter()
+                .map(|&(callee, call)| callee.map_or(0, |callee| levels[callee]).saturating_add(call + 1))
+                .fold(depth, usize::max))
+            .collect();
+
+        if next == levels {
+            break;
+        }
+
+        levels = next;
+    }
+
+    levels.into_iter().max().unwrap_or(0)
+}
+
+/// The stack for everything besides the nested levels, such as the builtins.
+const STACK_BASE: usize = 1024 * 1024;
+
+/// Run `f` on its own thread, with room on the stack for the given number
+/// of levels. Fails if there isn't enough memory for the stack.
+pub fn run_nested<T, F>(levels: usize, stack_per_level: usize, f: F) -> io::Result<T>
+    where F: FnOnce() -> T + Send, T: Send
+{
+    let stack_size = levels
+        .saturating_mul(stack_per_level)
+        .saturating_add(STACK_BASE);
+
+    std::thread::scope(|threads| {
+        let result = std::thread::Builder::new()
+            .stack_size(stack_size)
+            .spawn_scoped(threads, f)?
+            .join();
+
+        Ok(result.unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::parse;
+    use super::call_levels;
+
+    #[test]
+    fn test_call_levels() {
+        let program = parse("
+            function main() -> int { return 1 + f(); }
+            function f() -> int { if (true) { return g(); } return 0; }
+            function g() -> int { return 1 + (2 * 3); }
+        ").expect("Could not parse program");
+
+        // The calls of f and g are 3 deep, and g is 4 deep
+        assert_eq!(call_levels(&program, 1), 4);
+        assert_eq!(call_levels(&program, 2), 8);
+        assert_eq!(call_levels(&program, 3), 12);
+        assert_eq!(call_levels(&program, 1000), 12);
+
+        let program = parse("
+            function main() -> int { return 1 + main(); }
+        ").expect("Could not parse program");
+
+        // Each call of main is 3 deep, and so is the innermost one
+        assert_eq!(call_levels(&program, 1), 3);
+        assert_eq!(call_levels(&program, 1000), 999 * 4 + 3);
+        assert_eq!(call_levels(&program, usize::MAX), usize::MAX);
+    }
+}
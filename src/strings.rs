@@ -0,0 +1,209 @@
+use crate::parser::ast::Type;
+use crate::vm::error::ErrorKind;
+use crate::vm::native::NativeFunction;
+use crate::vm::value::Value;
+
+/// The string functions of the language, which every program can call.
+///
+/// They are native functions, so every pass handles them like the functions
+/// the host registers. They index strings by character, not by byte.
+pub fn natives() -> Vec<NativeFunction> {
+    vec![
+        // The number of characters in a string.
+        NativeFunction::new("len", vec![Type::String], Some(Type::Int), |args| match args {
+            [Value::String(text)] => Ok(Some(Value::Int(int(text.chars().count())?))),
+            _ => unreachable!("The arguments are checked"),
+        }),
+        // The characters from a start index up to, but not including, an end index.
+        NativeFunction::new("substring", vec![Type::String, Type::Int, Type::Int], Some(Type::String), |args| match args {
+            [Value::String(text), Value::Int(start), Value::Int(end)] => {
+                let length = text.chars().count();
+                let (start, end) = match (char_index(*start, length)?, char_index(*end, length)?) {
+                    (start, end) if start <= end => (start, end),
+                    _ => return Err(ErrorKind::InvalidRange { start: *start, end: *end }),
+                };
+
+                Ok(Some(Value::String(text.chars().skip(start).take(end - start).collect::<String>().into())))
+            }
+            _ => unreachable!("The arguments are checked"),
+        }).with_string_bytes(first_string_bytes),
+        // The index of the first occurrence of a string, or -1 if there is none.
+        NativeFunction::new("index_of", vec![Type::String, Type::String], Some(Type::Int), |args| match args {
+            [Value::String(text), Value::String(pattern)] => {
+                let index = match text.find(&**pattern) {
+                    Some(byte) => int(text[..byte].chars().count())?,
+                    None => -1,
+                };
+
+                Ok(Some(Value::Int(index)))
+            }
+            _ => unreachable!("The arguments are checked"),
+        }),
+        NativeFunction::new("contains", vec![Type::String, Type::String], Some(Type::Bool), |args| match args {
+            [Value::String(text), Value::String(pattern)] => Ok(Some(Value::Bool(text.contains(&**pattern)))),
+            _ => unreachable!("The arguments are checked"),
+        }),
+        NativeFunction::new("starts_with", vec![Type::String, Type::String], Some(Type::Bool), |args| match args {
+            [Value::String(text), Value::String(prefix)] => Ok(Some(Value::Bool(text.starts_with(&**prefix)))),
+            _ => unreachable!("The arguments are checked"),
+        }),
+        // The part of a string with the given index, when split at every separator.
+        NativeFunction::new("split", vec![Type::String, Type::String, Type::Int], Some(Type::String), |args| match args {
+            [Value::String(text), Value::String(separator), Value::Int(index)] => {
+                if separator.is_empty() {
+                    return Err(ErrorKind::EmptySeparator);
+                }
+
+                let part = usize::try_from(*index)
+                    .ok()
+                    .and_then(|index| text.split(&**separator).nth(index))
+                    .ok_or_else(|| ErrorKind::IndexOutOfRange {
+                        index: *index,
+                        length: text.split(&**separator).count(),
+                    })?;
+
+                Ok(Some(Value::String(part.into())))
+            }
+            _ => unreachable!("The arguments are checked"),
+        }).with_string_bytes(first_string_bytes),
+        // A string without leading and trailing whitespace.
+        NativeFunction::new("trim", vec![Type::String], Some(Type::String), |args| match args {
+            [Value::String(text)] => Ok(Some(Value::String(text.trim().into()))),
+            _ => unreachable!("The arguments are checked"),
+        }).with_string_bytes(first_string_bytes),
+        NativeFunction::new("to_upper", vec![Type::String], Some(Type::String), |args| match args {
+            [Value::String(text)] => Ok(Some(Value::String(text.to_uppercase().into()))),
+            _ => unreachable!("The arguments are checked"),
+        }).with_string_bytes(first_string_bytes),
+        NativeFunction::new("to_lower", vec![Type::String], Some(Type::String), |args| match args {
+            [Value::String(text)] => Ok(Some(Value::String(text.to_lowercase().into()))),
+            _ => unreachable!("The arguments are checked"),
+        }).with_string_bytes(first_string_bytes),
+        // A string repeated a number of times.
+        NativeFunction::new("repeat", vec![Type::String, Type::Int], Some(Type::String), |args| match args {
+            [Value::String(text), Value::Int(count)] => {
+                let count = usize::try_from(*count).map_err(|_| ErrorKind::NegativeCount(*count))?;
+
+                // Strings can't be larger than isize::MAX bytes
+                text.len()
+                    .checked_mul(count)
+                    .filter(|bytes| isize::try_from(*bytes).is_ok())
+                    .ok_or(ErrorKind::IntegerOverflow)?;
+
+                Ok(Some(Value::String(text.repeat(count).into())))
+            }
+            _ => unreachable!("The arguments are checked"),
+        }).with_string_bytes(|args| match args {
+            [Value::String(text), Value::Int(count)] => text.len().saturating_mul((*count).max(0) as usize),
+            _ => 0,
+        }),
+    ]
+}
+
+/// The signatures of the string functions, for the passes that only check the calls.
+#[cfg(test)]
+pub fn signatures() -> Vec<crate::parser::ast::Signature> {
+    natives().into_iter().map(|native| native.signature).collect()
+}
+
+/// The functions that build a string from the first one build at most as many bytes.
+fn first_string_bytes(args: &[Value]) -> usize {
+    match args {
+        [Value::String(text), ..] => text.len(),
+        _ => 0,
+    }
+}
+
+/// Convert a character index of a range, which may be one past the last
+/// character for the end.
+fn char_index(index: i64, length: usize) -> Result<usize, ErrorKind> {
+    usize::try_from(index)
+        .ok()
+        .filter(|index| *index <= length)
+        .ok_or(ErrorKind::IndexOutOfRange { index, length })
+}
+
+fn int(value: usize) -> Result<i64, ErrorKind> {
+    i64::try_from(value).map_err(|_| ErrorKind::IntegerOverflow)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::ast::Type;
+    use crate::vm::error::ErrorKind;
+    use crate::vm::function::run_native;
+    use crate::vm::value::Value;
+    use super::natives;
+
+    fn call(name: &str, args: &[Value]) -> Result<Option<Value>, ErrorKind> {
+        let native = natives().into_iter().find(|native| native.name() == name).expect("The function should exist");
+
+        run_native(&native, args.to_vec()).map_err(|error| error.kind)
+    }
+
+    fn string(text: &str) -> Value {
+        Value::String(text.into())
+    }
+
+    #[test]
+    fn test_strings() {
+        let cases = [
+            ("len", vec![string("héllo 🌍")], Some(Value::Int(7))),
+            ("len", vec![string("")], Some(Value::Int(0))),
+            ("substring", vec![string("héllo 🌍"), Value::Int(1), Value::Int(7)], string("éllo 🌍").into()),
+            ("substring", vec![string("héllo"), Value::Int(2), Value::Int(2)], string("").into()),
+            ("index_of", vec![string("🌍 héllo"), string("llo")], Some(Value::Int(4))),
+            ("index_of", vec![string("hello"), string("x")], Some(Value::Int(-1))),
+            ("contains", vec![string("hello"), string("ell")], Value::Bool(true).into()),
+            ("contains", vec![string("hello"), string("")], Value::Bool(true).into()),
+            ("starts_with", vec![string("hello"), string("ell")], Value::Bool(false).into()),
+            ("starts_with", vec![string("hello"), string("he")], Value::Bool(true).into()),
+            ("split", vec![string("a,b,,c"), string(","), Value::Int(0)], string("a").into()),
+            ("split", vec![string("a,b,,c"), string(","), Value::Int(2)], string("").into()),
+            ("split", vec![string("a::b"), string("::"), Value::Int(1)], string("b").into()),
+            ("trim", vec![string(" \t hi \n")], string("hi").into()),
+            ("to_upper", vec![string("straße")], string("STRASSE").into()),
+            ("to_lower", vec![string("ÀB")], string("àb").into()),
+            ("repeat", vec![string("ab"), Value::Int(3)], string("ababab").into()),
+            ("repeat", vec![string("ab"), Value::Int(0)], string("").into()),
+        ];
+
+        for (name, args, expected) in cases {
+            assert_eq!(call(name, &args), Ok(expected), "{name} {args:?}");
+        }
+    }
+
+    #[test]
+    fn test_string_errors() {
+        let cases = [
+            ("substring", vec![string("héllo"), Value::Int(0), Value::Int(6)], ErrorKind::IndexOutOfRange { index: 6, length: 5 }),
+            ("substring", vec![string("héllo"), Value::Int(-1), Value::Int(2)], ErrorKind::IndexOutOfRange { index: -1, length: 5 }),
+            ("substring", vec![string("héllo"), Value::Int(3), Value::Int(2)], ErrorKind::InvalidRange { start: 3, end: 2 }),
+            ("split", vec![string("a,b"), string(","), Value::Int(2)], ErrorKind::IndexOutOfRange { index: 2, length: 2 }),
+            ("split", vec![string("a,b"), string(","), Value::Int(-1)], ErrorKind::IndexOutOfRange { index: -1, length: 2 }),
+            ("split", vec![string("a,b"), string(""), Value::Int(0)], ErrorKind::EmptySeparator),
+            ("repeat", vec![string("a"), Value::Int(-2)], ErrorKind::NegativeCount(-2)),
+            ("repeat", vec![string("ab"), Value::Int(4611686018427387904)], ErrorKind::IntegerOverflow),
+            ("repeat", vec![string("ab"), Value::Int(i64::MAX)], ErrorKind::IntegerOverflow),
+            ("len", vec![Value::Int(1)], ErrorKind::TypeMismatch {
+                context: "argument 1 of function len".into(),
+                expected: Type::String,
+                found: Type::Int,
+            }),
+        ];
+
+        for (name, args, expected) in cases {
+            assert_eq!(call(name, &args), Err(expected), "{name} {args:?}");
+        }
+    }
+
+    #[test]
+    fn test_string_bytes() {
+        let native = |name: &str| natives().into_iter().find(|native| native.name() == name).expect("The function should exist");
+
+        assert_eq!(native("repeat").string_bytes(&[string("ab"), Value::Int(3)]), 6);
+        assert_eq!(native("repeat").string_bytes(&[string("ab"), Value::Int(-3)]), 0);
+        assert_eq!(native("trim").string_bytes(&[string(" ab ")]), 4);
+        assert_eq!(native("len").string_bytes(&[string("ab")]), 0);
+    }
+}
@@ -0,0 +1,83 @@
+use crate::parser::ast::{BlockNode, Expression, ExpressionNode, Spanned, Statement, Type};
+use super::error::ErrorKind;
+use super::Checker;
+
+impl Checker<'_> {
+    pub(super) fn check_block(&mut self, block: &[Statement]) {
+        for node in block {
+            self.check_statement(node);
+        }
+    }
+
+    fn check_statement(&mut self, node: &Statement) {
+        match &node.node {
+            BlockNode::VariableDefinition { name, type_name, value } => {
+                if let Some(found) = self.check_expression(value) {
+                    self.expect(|| format!("variable {name}"), *type_name, found, value.span);
+                }
+
+                self.add_variable(&name.name, *type_name);
+            }
+            BlockNode::Assignment { lhs, rhs } => {
+                let found = self.check_expression(rhs);
+
+                match (self.get_variable(&lhs.name), found) {
+                    (Some(expected), Some(found)) => {
+                        self.expect(|| format!("assignment to {lhs}"), expected, found, rhs.span);
+                    }
+                    (None, _) => self.error(ErrorKind::UndefinedVariable(lhs.name.clone()), node.span),
+                    (Some(_), None) => {}
+                }
+            }
+            BlockNode::Expression(Spanned { node: ExpressionNode::Call { name, args }, span }) => {
+                // Calls to functions without a return value are allowed as statements
+                self.check_call(name, args, *span);
+            }
+            BlockNode::Expression(expr) => {
+                self.check_expression(expr);
+            }
+            BlockNode::Block(nested) => {
+                self.subscope(|checker| checker.check_block(nested));
+            }
+            BlockNode::IfStatement { condition, block, else_block } => {
+                self.check_condition(condition, "if-statement");
+                self.subscope(|checker| checker.check_block(block));
+
+                if let Some(else_block) = else_block {
+                    self.subscope(|checker| checker.check_block(else_block));
+                }
+            }
+            BlockNode::WhileLoop { condition, block } => {
+                self.check_condition(condition, "while-loop");
+                self.subscope(|checker| checker.check_block(block));
+            }
+            BlockNode::Break | BlockNode::Continue => {}
+            BlockNode::Return(expr) => {
+                let found = self.check_expression(expr);
+                let function = self.function.expect("Return outside a function");
+
+                match (function.return_type, found) {
+                    (Some(expected), Some(found)) => {
+                        self.expect(
+                            || format!("return value of function {}", function.name),
+                            expected,
+                            found,
+                            expr.span,
+                        );
+                    }
+                    (None, _) => {
+                        self.error(ErrorKind::UnexpectedReturnValue(function.name.clone()), node.span);
+                    }
+                    (Some(_), None) => {}
+                }
+            }
+        }
+    }
+
+    fn check_condition(&mut self, condition: &Expression, statement: &'static str) {
+        match self.check_expression(condition) {
+            Some(Type::Bool) | None => {}
+            Some(found) => self.error(ErrorKind::InvalidCondition { statement, found }, condition.span),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::fmt::{Display, Formatter};
+use crate::parser::ast::{Ident, Span, Type};
+
+/// What is wrong with the types in a program.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ErrorKind {
+    UndefinedVariable(Ident),
+    UndefinedFunction(Ident),
+    /// A value of the wrong type, for example in an assignment or as an argument.
+    TypeMismatch {
+        context: String,
+        expected: Type,
+        found: Type,
+    },
+    /// A binary operator applied to types it isn't defined for.
+    InvalidOperands {
+        operator: &'static str,
+        lhs: Type,
+        rhs: Type,
+    },
+    /// A unary operator applied to a type it isn't defined for.
+    InvalidOperand {
+        operator: &'static str,
+        operand: Type,
+    },
+    Arity {
+        function: Ident,
+        expected: usize,
+        found: usize,
+    },
+    /// A condition that isn't a bool.
+    InvalidCondition {
+        statement: &'static str,
+        found: Type,
+    },
+    /// A function without a return type used as a value.
+    NoReturnValue(Ident),
+    /// A return statement in a function without a return type.
+    UnexpectedReturnValue(Ident),
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UndefinedVariable(name) => write!(f, "Undefined variable {name}"),
+            Self::UndefinedFunction(name) => write!(f, "Undefined function {name}"),
+            Self::TypeMismatch { context, expected, found } => {
+                write!(f, "Mismatched types for {context}: expected {expected}, found {found}")
+            }
+            Self::InvalidOperands { operator, lhs, rhs } => {
+                write!(f, "Operator {operator} can not be applied to {lhs} and {rhs}")
+            }
+            Self::InvalidOperand { operator, operand } => {
+                write!(f, "Unary operator {operator} can not be applied to {operand}")
+            }
+            Self::Arity { function, expected, found } => {
+                write!(f, "Function {function} expected {expected} arguments, got {found}")
+            }
+            Self::InvalidCondition { statement, found } => {
+                write!(f, "The condition of the {statement} must be bool, found {found}")
+            }
+            Self::NoReturnValue(name) => write!(f, "Function {name} does not return a value"),
+            Self::UnexpectedReturnValue(name) => {
+                write!(f, "Function {name} returns a value, but it doesn't have a return type")
+            }
+        }
+    }
+}
+
+/// A type error, with the node it was found in.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TypeError {
+    pub kind: ErrorKind,
+    pub span: Span,
+}
+
+impl TypeError {
+    /// Format the error with the location prefixed by the file name.
+    pub fn report(&self, file: &str) -> String {
+        format!("{file}:{}: error: {}", self.span, self.kind)
+    }
+}
+
+impl Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.span, self.kind)
+    }
+}
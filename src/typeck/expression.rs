@@ -0,0 +1,176 @@
+use crate::builtin::Builtin;
+use crate::parser::ast::{BinaryVerb, Expression, ExpressionNode, Span, TermNode, Type, UnaryVerb};
+use super::error::ErrorKind;
+use super::Checker;
+
+impl Checker<'_> {
+    /// Find the type of an expression.
+    ///
+    /// Returns `None` if the type can't be known because of an error, which
+    /// has then already been reported.
+    pub(super) fn check_expression(&mut self, expr: &Expression) -> Option<Type> {
+        match &expr.node {
+            ExpressionNode::BinaryOperation { verb, lhs, rhs } => {
+                // Check both sides before giving up, to report the errors in both
+                let lhs = self.check_expression(lhs);
+                let rhs = self.check_expression(rhs);
+                let (lhs, rhs) = (lhs?, rhs?);
+
+                let result = binary_type(verb, lhs, rhs);
+
+                if result.is_none() {
+                    self.error(ErrorKind::InvalidOperands { operator: verb.symbol(), lhs, rhs }, expr.span);
+                }
+
+                result
+            }
+            ExpressionNode::Unary { op, operand } => {
+                let operand = self.check_expression(operand)?;
+
+                let result = unary_type(op, operand);
+
+                if result.is_none() {
+                    self.error(ErrorKind::InvalidOperand { operator: op.symbol(), operand }, expr.span);
+                }
+
+                result
+            }
+            ExpressionNode::Call { name, args } => {
+                let function = self.check_call(name, args, expr.span)?;
+
+                if function.is_none() {
+                    self.error(ErrorKind::NoReturnValue(name.clone()), expr.span);
+                }
+
+                function
+            }
+            ExpressionNode::Term(term) => match term {
+                TermNode::Variable(variable) => {
+                    let type_name = self.get_variable(&variable.name);
+
+                    if type_name.is_none() {
+                        self.error(ErrorKind::UndefinedVariable(variable.name.clone()), expr.span);
+                    }
+
+                    type_name
+                }
+                TermNode::Boolean(_) => Some(Type::Bool),
+                TermNode::Integer(_) => Some(Type::Int),
+                TermNode::String(_) => Some(Type::String),
+            },
+        }
+    }
+
+    /// Check the arguments of a call, and find its return type.
+    ///
+    /// Returns `None` if the function is undefined, and `Some(None)` if it
+    /// doesn't have a return type.
+    pub(super) fn check_call(&mut self, name: &str, args: &[Expression], span: Span) -> Option<Option<Type>> {
+        let arg_types: Vec<_> = args.iter().map(|arg| self.check_expression(arg)).collect();
+
+        // The description and type of each parameter, where `None` accepts any type
+        let (parameters, return_type): (Vec<_>, _) = if let Some(function) = self.functions.get(name).copied() {
+            let parameters = function.parameters
+                .iter()
+                .map(|param| (format!("parameter {} of function {name}", param.name), Some(param.type_name)))
+                .collect();
+
+            (parameters, function.return_type)
+        } else if let Some(native) = self.natives.get(name).copied() {
+            let parameters = native.parameters
+                .iter()
+                .enumerate()
+                .map(|(index, type_name)| (format!("argument {} of function {name}", index + 1), Some(*type_name)))
+                .collect();
+
+            (parameters, native.return_type)
+        } else if let Some(builtin) = Builtin::from_name(name) {
+            let parameters = builtin.parameters()
+                .iter()
+                .enumerate()
+                .map(|(index, type_name)| (format!("argument {} of function {name}", index + 1), *type_name))
+                .collect();
+
+            (parameters, builtin.return_type())
+        } else {
+            self.error(ErrorKind::UndefinedFunction(name.to_string()), span);
+            return None;
+        };
+
+        if parameters.len() != args.len() {
+            self.error(
+                ErrorKind::Arity {
+                    function: name.to_string(),
+                    expected: parameters.len(),
+                    found: args.len(),
+                },
+                span,
+            );
+        } else {
+            for ((context, expected), (arg, found)) in parameters.into_iter().zip(args.iter().zip(arg_types)) {
+                if let (Some(expected), Some(found)) = (expected, found) {
+                    self.expect(|| context, expected, found, arg.span);
+                }
+            }
+        }
+
+        Some(return_type)
+    }
+}
+
+/// The type of a binary operation, or `None` if the operator can't be applied to the operands.
+fn binary_type(verb: &BinaryVerb, lhs: Type, rhs: Type) -> Option<Type> {
+    match (verb, lhs, rhs) {
+        (BinaryVerb::Plus, Type::Int, Type::Int) => Some(Type::Int),
+        (BinaryVerb::Plus, Type::String, Type::String) => Some(Type::String),
+        (
+            BinaryVerb::Minus | BinaryVerb::Multiply | BinaryVerb::Divide | BinaryVerb::Modulo
+            | BinaryVerb::WrappingPlus | BinaryVerb::WrappingMinus | BinaryVerb::WrappingMultiply,
+            Type::Int,
+            Type::Int,
+        ) => Some(Type::Int),
+        (BinaryVerb::Compare | BinaryVerb::NotEqual, lhs, rhs) if lhs == rhs => Some(Type::Bool),
+        (
+            BinaryVerb::Less | BinaryVerb::LessEqual | BinaryVerb::Greater | BinaryVerb::GreaterEqual,
+            Type::Int,
+            Type::Int,
+        ) => Some(Type::Bool),
+        (BinaryVerb::And | BinaryVerb::Or, Type::Bool, Type::Bool) => Some(Type::Bool),
+        _ => None,
+    }
+}
+
+/// The type of a unary operation, or `None` if the operator can't be applied to the operand.
+fn unary_type(op: &UnaryVerb, operand: Type) -> Option<Type> {
+    match (op, operand) {
+        (UnaryVerb::Plus | UnaryVerb::Minus, Type::Int) => Some(Type::Int),
+        (UnaryVerb::Not, Type::Bool) => Some(Type::Bool),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::ast::{BinaryVerb, Type, UnaryVerb};
+    use super::{binary_type, unary_type};
+
+    #[test]
+    fn test_binary_type() {
+        assert_eq!(binary_type(&BinaryVerb::Plus, Type::String, Type::String), Some(Type::String));
+        assert_eq!(binary_type(&BinaryVerb::Minus, Type::String, Type::String), None);
+        assert_eq!(binary_type(&BinaryVerb::Compare, Type::Bool, Type::Bool), Some(Type::Bool));
+        assert_eq!(binary_type(&BinaryVerb::Compare, Type::Bool, Type::Int), None);
+        assert_eq!(binary_type(&BinaryVerb::Less, Type::Int, Type::Int), Some(Type::Bool));
+        assert_eq!(binary_type(&BinaryVerb::Less, Type::Bool, Type::Bool), None);
+        assert_eq!(binary_type(&BinaryVerb::And, Type::Bool, Type::Bool), Some(Type::Bool));
+        assert_eq!(binary_type(&BinaryVerb::And, Type::Int, Type::Int), None);
+    }
+
+    #[test]
+    fn test_unary_type() {
+        assert_eq!(unary_type(&UnaryVerb::Minus, Type::Int), Some(Type::Int));
+        assert_eq!(unary_type(&UnaryVerb::Minus, Type::Bool), None);
+        assert_eq!(unary_type(&UnaryVerb::Not, Type::Bool), Some(Type::Bool));
+        assert_eq!(unary_type(&UnaryVerb::Not, Type::Int), None);
+    }
+}
@@ -0,0 +1,107 @@
+mod error;
+mod block;
+mod expression;
+
+#[cfg(test)]
+mod test;
+
+use std::collections::HashMap;
+use crate::parser::ast::{FunctionNode, Ident, Program, Signature, Span, Type};
+pub use self::error::{ErrorKind, TypeError};
+
+/// Check the types of the whole program, and return every error found.
+///
+/// This follows the same scoping rules as the VM, but checks every branch,
+/// so errors are found even in code that never runs.
+#[cfg(test)]
+pub fn check(program: &Program) -> Result<(), Vec<TypeError>> {
+    check_with_natives(program, &crate::strings::signatures())
+}
+
+/// Check a program that can also call the given native functions.
+pub fn check_with_natives(program: &Program, natives: &[Signature]) -> Result<(), Vec<TypeError>> {
+    let mut checker = Checker::new(program, natives);
+
+    for function in &program.nodes {
+        checker.check_function(function);
+    }
+
+    if checker.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(checker.errors)
+    }
+}
+
+struct Checker<'a> {
+    functions: HashMap<&'a str, &'a FunctionNode>,
+    natives: HashMap<&'a str, &'a Signature>,
+    /// The variables of the function being checked, innermost block last.
+    layers: Vec<HashMap<Ident, Type>>,
+    /// The function being checked.
+    function: Option<&'a FunctionNode>,
+    errors: Vec<TypeError>,
+}
+
+impl<'a> Checker<'a> {
+    fn new(program: &'a Program, natives: &'a [Signature]) -> Self {
+        Checker {
+            functions: program.nodes
+                .iter()
+                .map(|function| (function.name.as_str(), &**function))
+                .collect(),
+            natives: natives
+                .iter()
+                .map(|native| (native.name.as_str(), native))
+                .collect(),
+            layers: vec![],
+            function: None,
+            errors: vec![],
+        }
+    }
+
+    fn error(&mut self, kind: ErrorKind, span: Span) {
+        self.errors.push(TypeError { kind, span });
+    }
+
+    /// Report a type mismatch, unless the types are equal.
+    fn expect(&mut self, context: impl FnOnce() -> String, expected: Type, found: Type, span: Span) {
+        if expected != found {
+            self.error(ErrorKind::TypeMismatch { context: context(), expected, found }, span);
+        }
+    }
+
+    fn subscope(&mut self, f: impl FnOnce(&mut Self)) {
+        self.layers.push(HashMap::new());
+        f(self);
+        self.layers.pop();
+    }
+
+    fn add_variable(&mut self, name: &str, type_name: Type) {
+        self.layers
+            .last_mut()
+            .expect("Variable defined outside a function")
+            .insert(name.to_string(), type_name);
+    }
+
+    fn get_variable(&self, name: &str) -> Option<Type> {
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|layer| layer.get(name).copied())
+    }
+
+    fn check_function(&mut self, function: &'a FunctionNode) {
+        self.function = Some(function);
+
+        self.subscope(|checker| {
+            for param in &function.parameters {
+                checker.add_variable(&param.name, param.type_name);
+            }
+
+            checker.check_block(&function.block);
+        });
+
+        self.function = None;
+    }
+}
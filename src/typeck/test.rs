@@ -0,0 +1,124 @@
+use crate::parser::ast::Type;
+use crate::parser::parse;
+use super::{check, ErrorKind};
+
+/// Parse and check a program, and return the kinds and lines of the errors.
+fn errors(source: &str) -> Vec<(ErrorKind, usize)> {
+    let program = parse(source).expect("Could not parse program");
+
+    check(&program)
+        .err()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|error| (error.kind, error.span.line))
+        .collect()
+}
+
+#[test]
+fn test_example_programs() {
+    for source in [
+        include_str!("../../tests/arithmetic.kw"),
+        include_str!("../../tests/expression.kw"),
+        include_str!("../../tests/function_call.kw"),
+        include_str!("../../tests/if_else.kw"),
+        include_str!("../../tests/if_statement.kw"),
+        include_str!("../../tests/logic.kw"),
+        include_str!("../../tests/simple_return.kw"),
+        include_str!("../../tests/variable_definition.kw"),
+        include_str!("../../tests/while_loop.kw"),
+    ] {
+        assert_eq!(errors(source), vec![]);
+    }
+}
+
+#[test]
+fn test_untaken_branch() {
+    let errors = errors("
+        function main() -> int {
+            if (false) {
+                var x: int = \"s\";
+            }
+
+            return 0;
+        }
+    ");
+
+    assert_eq!(
+        errors,
+        vec![
+            (ErrorKind::TypeMismatch { context: "variable x".into(), expected: Type::Int, found: Type::String }, 4),
+        ],
+    );
+}
+
+#[test]
+fn test_all_errors_reported() {
+    let errors = errors("
+        function main() -> int {
+            var x: int = 1;
+            x = true;
+            var y: bool = x + \"s\";
+
+            while (x) {
+                x = -true;
+            }
+
+            double(1, 2);
+            double(false);
+            missing(x);
+
+            return noop();
+        }
+
+        function double(x: int) -> int {
+            return x == 2;
+        }
+
+        function noop() {
+            return 1;
+        }
+    ");
+
+    assert_eq!(
+        errors,
+        vec![
+            (ErrorKind::TypeMismatch { context: "assignment to x".into(), expected: Type::Int, found: Type::Bool }, 4),
+            (ErrorKind::InvalidOperands { operator: "+", lhs: Type::Int, rhs: Type::String }, 5),
+            (ErrorKind::InvalidCondition { statement: "while-loop", found: Type::Int }, 7),
+            (ErrorKind::InvalidOperand { operator: "-", operand: Type::Bool }, 8),
+            (ErrorKind::Arity { function: "double".into(), expected: 1, found: 2 }, 11),
+            (ErrorKind::TypeMismatch { context: "parameter x of function double".into(), expected: Type::Int, found: Type::Bool }, 12),
+            (ErrorKind::UndefinedFunction("missing".into()), 13),
+            (ErrorKind::NoReturnValue("noop".into()), 15),
+            (ErrorKind::TypeMismatch { context: "return value of function double".into(), expected: Type::Int, found: Type::Bool }, 19),
+            (ErrorKind::UnexpectedReturnValue("noop".into()), 23),
+        ],
+    );
+}
+
+#[test]
+fn test_scopes() {
+    let errors = errors("
+        function main() -> int {
+            if (true) {
+                var x: int = 1;
+            }
+
+            return x + helper(z);
+        }
+
+        function helper() -> int {
+            return y;
+        }
+    ");
+
+    assert_eq!(
+        errors,
+        vec![
+            (ErrorKind::UndefinedVariable("x".into()), 7),
+            (ErrorKind::UndefinedVariable("z".into()), 7),
+            (ErrorKind::Arity { function: "helper".into(), expected: 0, found: 1 }, 7),
+            (ErrorKind::UndefinedVariable("y".into()), 11),
+        ],
+    );
+}
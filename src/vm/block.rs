@@ -1,76 +1,141 @@
-use crate::parser::ast::BlockNode;
-use crate::vm::expression::run_expression;
+use crate::parser::ast::{BlockNode, Expression, ExpressionNode, Spanned, Statement};
+use crate::vm::error::{ErrorKind, RuntimeError};
+use crate::vm::expression::{run_call, run_expression};
 use crate::vm::scope::Scope;
 use crate::vm::value::Value;
 
-pub fn run_block(block: &Vec<BlockNode>, scope: &mut Scope) -> Result<Option<Value>, String> {
-    let mut return_value = None;
+/// How execution should continue after running a block.
+#[derive(Debug, PartialEq)]
+pub enum ControlFlow {
+    /// Continue with the next statement.
+    Next,
+    /// Exit the innermost loop.
+    Break,
+    /// Skip to the next iteration of the innermost loop.
+    Continue,
+    /// Return from the function with the given value.
+    Return(Value),
+}
 
+pub fn run_block(block: &Vec<Statement>, scope: &mut Scope) -> Result<ControlFlow, RuntimeError> {
     for node in block {
-        match node {
-            BlockNode::VariableDefinition { name, type_name, value } => {
-                if scope.get_variable(name).is_none() {
-                    let v = run_expression(value, scope)?;
-                    if v.ast_type() == *type_name {
-                        scope.add_variable(name, v);
-                    } else {
-                        Err(format!("Invalid variable definition. Mismatch types {} and {}", v.ast_type(), type_name))?;
-                    }
-                } else {
-                    Err(format!("Definition of {} shadows previously declared variable", name))?;
-                }
+        scope.statement().map_err(|error| RuntimeError::from(error).at(node.span))?;
+
+        let flow = run_statement(&node.node, scope).map_err(|error| error.at(node.span))?;
+
+        if flow != ControlFlow::Next {
+            return Ok(flow);
+        }
+    }
+
+    Ok(ControlFlow::Next)
+}
+
+fn run_statement(node: &BlockNode, scope: &mut Scope) -> Result<ControlFlow, RuntimeError> {
+    let flow = match node {
+        BlockNode::VariableDefinition { name, type_name, value } => {
+            let v = run_expression(value, scope)?;
+            let slot = name.slot.ok_or(ErrorKind::UndefinedVariable(name.name.clone()))?;
+
+            if v.ast_type() == *type_name {
+                scope.add_variable(slot, v);
+            } else {
+                Err(ErrorKind::TypeMismatch {
+                    context: format!("variable {name}"),
+                    expected: *type_name,
+                    found: v.ast_type(),
+                })?;
+            }
+
+            ControlFlow::Next
+        },
+        BlockNode::Assignment { lhs, rhs } => {
+            let v = scope.get_variable(lhs)?.ast_type();
+            let e = run_expression(rhs, scope)?;
 
-            },
-            BlockNode::Assignment { lhs, rhs } => {
-                let v = scope.get_variable(lhs).ok_or("Hello".to_string())?;
-                let e = run_expression(rhs, scope)?;
+            if e.ast_type() == v {
+                scope.set_variable(lhs, e)?;
+            } else {
+                Err(ErrorKind::TypeMismatch {
+                    context: format!("assignment to {lhs}"),
+                    expected: v,
+                    found: e.ast_type(),
+                })?;
+            }
+
+            ControlFlow::Next
+        },
+        BlockNode::Expression(Spanned { node: ExpressionNode::Call { name, args }, .. }) => {
+            run_call(name, args, scope)?;
+            ControlFlow::Next
+        },
+        BlockNode::Expression(expr) => {
+            run_expression(expr, scope)?;
+            ControlFlow::Next
+        },
+        BlockNode::IfStatement { condition, block, else_block } => {
+            if run_condition(condition, scope, "if-statement")? {
+                run_block(block, scope)?
+            } else if let Some(else_block) = else_block {
+                run_block(else_block, scope)?
+            } else {
+                ControlFlow::Next
+            }
+        }
+        BlockNode::WhileLoop { condition, block } => {
+            let mut flow = ControlFlow::Next;
+
+            loop {
+                // Loops with an empty body have to run out of statements as well
+                scope.statement().map_err(|error| RuntimeError::from(error).at(condition.span))?;
 
-                if e.ast_type() == v.ast_type() {
-                    scope.set_variable(lhs, e)?;
-                } else {
-                    Err("Mismatch in assigment".to_string())?;
+                if !run_condition(condition, scope, "while-loop")? {
+                    break;
                 }
-            },
-            BlockNode::Expression(expr) => {
-                run_expression(expr, &scope)?;
-            },
-            BlockNode::IfStatement { condition, block } => {
-                match run_expression(condition, &scope)? {
-                    Value::Bool(result) => {
-                        if result {
-                            return_value = scope.subscope(|s| run_block(block, s))?
-                        }
-                    }
-                    x => {
-                        Err(format!("Invalid condition result {x} in if-statement"))?
-                    }
+
+                match run_block(block, scope)? {
+                    ControlFlow::Next | ControlFlow::Continue => {},
+                    ControlFlow::Break => break,
+                    ControlFlow::Return(value) => {
+                        flow = ControlFlow::Return(value);
+                        break;
+                    },
                 }
             }
-            BlockNode::Block(nested) => {
-                return_value = scope.subscope(|s| run_block(nested, s))?;
-            },
-            BlockNode::Return(expr) => {
-                return_value = Some(
-                    scope.subscope(|s| run_expression(expr, s))?
-                );
-            }
-        };
 
-        if return_value.is_some() {
-            break;
+            flow
         }
-    }
+        BlockNode::Break => ControlFlow::Break,
+        BlockNode::Continue => ControlFlow::Continue,
+        BlockNode::Block(nested) => {
+            run_block(nested, scope)?
+        },
+        BlockNode::Return(expr) => {
+            ControlFlow::Return(run_expression(expr, scope)?)
+        }
+    };
 
-    Ok(return_value)
+    Ok(flow)
+}
+
+fn run_condition(condition: &Expression, scope: &mut Scope, statement: &'static str) -> Result<bool, RuntimeError> {
+    match run_expression(condition, scope)? {
+        Value::Bool(result) => Ok(result),
+        x => Err(RuntimeError::from(ErrorKind::InvalidCondition { statement, found: x.ast_type() }).at(condition.span)),
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::parser::ast::{BinaryVerb, BlockNode, ExpressionNode, TermNode, Type};
-    use crate::vm::block::run_block;
+    use crate::parser::ast::{BinaryVerb, BlockNode, ExpressionNode, TermNode, Type, Variable};
+    use crate::vm::block::{ControlFlow, run_block};
     use crate::vm::scope::Scope;
     use crate::vm::value::Value;
 
+    fn var(name: &str, slot: usize) -> Variable {
+        Variable { name: name.into(), slot: Some(slot) }
+    }
+
     #[test]
     fn test_variable_definition() {
         let mut scope = Scope::new();
@@ -78,29 +143,29 @@ mod test {
         let result = run_block(
             &vec![
                 BlockNode::VariableDefinition {
-                    name: "x".into(),
+                    name: var("x", 0),
                     type_name: Type::Int,
-                    value: ExpressionNode::Term(TermNode::Integer(5)),
-                },
+                    value: ExpressionNode::Term(TermNode::Integer(5)).into(),
+                }.into(),
                 BlockNode::Return(
-                    ExpressionNode::Term(TermNode::Variable("x".into()))
-                )
+                    ExpressionNode::Term(TermNode::Variable(var("x", 0))).into()
+                ).into()
             ],
             &mut scope,
         ).expect("Error while defining simple variable");
 
-        assert_eq!(result, Some(Value::Int(5)));
+        assert_eq!(result, ControlFlow::Return(Value::Int(5)));
 
         run_block(
             &vec![
                 BlockNode::VariableDefinition {
-                    name: "x".into(),
+                    name: var("x", 0),
                     type_name: Type::Int,
-                    value: ExpressionNode::Term(TermNode::String("test".into())),
-                },
+                    value: ExpressionNode::Term(TermNode::String("test".into())).into(),
+                }.into(),
                 BlockNode::Return(
-                    ExpressionNode::Term(TermNode::Variable("x".into()))
-                )
+                    ExpressionNode::Term(TermNode::Variable(var("x", 0))).into()
+                ).into()
             ],
             &mut scope,
         ).expect_err("The value of the variable should be required to be int");
@@ -113,22 +178,22 @@ mod test {
         let result = run_block(
             &vec![
                 BlockNode::VariableDefinition {
-                    name: "x".into(),
+                    name: var("x", 0),
                     type_name: Type::Int,
-                    value: ExpressionNode::Term(TermNode::Integer(3)),
-                },
+                    value: ExpressionNode::Term(TermNode::Integer(3)).into(),
+                }.into(),
                 BlockNode::Assignment {
-                    lhs: "x".into(),
-                    rhs: ExpressionNode::Term(TermNode::Integer(5)),
-                },
+                    lhs: var("x", 0),
+                    rhs: ExpressionNode::Term(TermNode::Integer(5)).into(),
+                }.into(),
                 BlockNode::Return(
-                    ExpressionNode::Term(TermNode::Variable("x".into()))
-                )
+                    ExpressionNode::Term(TermNode::Variable(var("x", 0))).into()
+                ).into()
             ],
             &mut scope,
         ).expect("Error with assignment");
 
-        assert_eq!(result, Some(Value::Int(5)));
+        assert_eq!(result, ControlFlow::Return(Value::Int(5)));
     }
 
     #[test]
@@ -137,12 +202,12 @@ mod test {
 
         let result = run_block(
             &vec![
-                BlockNode::Block(vec![]),
+                BlockNode::Block(vec![]).into(),
             ],
             &mut scope,
         ).expect("Error with nested block");
 
-        assert_eq!(result, None)
+        assert_eq!(result, ControlFlow::Next)
     }
 
     #[test]
@@ -152,22 +217,22 @@ mod test {
         let result = run_block(
             &vec![
                 BlockNode::VariableDefinition {
-                    name: "x".into(),
+                    name: var("x", 0),
                     type_name: Type::Int,
-                    value: ExpressionNode::Term(TermNode::Integer(3)),
-                },
+                    value: ExpressionNode::Term(TermNode::Integer(3)).into(),
+                }.into(),
                 BlockNode::Block(vec![
                     BlockNode::VariableDefinition {
-                        name: "y".into(),
+                        name: var("y", 1),
                         type_name: Type::Int,
-                        value: ExpressionNode::Term(TermNode::Variable("x".to_string())),
-                    },
-                ])
+                        value: ExpressionNode::Term(TermNode::Variable(var("x", 0))).into(),
+                    }.into(),
+                ]).into()
             ],
             &mut scope,
         ).expect("Error with nested block");
 
-        assert_eq!(result, None)
+        assert_eq!(result, ControlFlow::Next)
     }
 
     #[test]
@@ -178,17 +243,17 @@ mod test {
             &vec![
                 BlockNode::Block(vec![
                     BlockNode::Return(
-                        ExpressionNode::Term(TermNode::Integer(1))
-                    )
-                ]),
+                        ExpressionNode::Term(TermNode::Integer(1)).into()
+                    ).into()
+                ]).into(),
                 BlockNode::Return(
-                    ExpressionNode::Term(TermNode::Integer(2))
-                )
+                    ExpressionNode::Term(TermNode::Integer(2)).into()
+                ).into()
             ],
             &mut scope,
         ).expect("Error with nested block");
 
-        assert_eq!(result, Some(Value::Int(1)))
+        assert_eq!(result, ControlFlow::Return(Value::Int(1)))
     }
 
     #[test]
@@ -198,21 +263,22 @@ mod test {
         let result = run_block(
             &vec![
                 BlockNode::IfStatement {
-                    condition: ExpressionNode::Term(TermNode::Boolean(true)),
+                    condition: ExpressionNode::Term(TermNode::Boolean(true)).into(),
                     block: vec![
                         BlockNode::Return(
-                            ExpressionNode::Term(TermNode::Integer(2))
-                        ),
+                            ExpressionNode::Term(TermNode::Integer(2)).into()
+                        ).into(),
                     ],
-                },
+                    else_block: None,
+                }.into(),
                 BlockNode::Return(
-                    ExpressionNode::Term(TermNode::Integer(0))
-                ),
+                    ExpressionNode::Term(TermNode::Integer(0)).into()
+                ).into(),
             ],
             &mut scope,
         ).expect("Error with nested block");
 
-        assert_eq!(result, Some(Value::Int(2)))
+        assert_eq!(result, ControlFlow::Return(Value::Int(2)))
     }
 
     #[test]
@@ -222,32 +288,179 @@ mod test {
         let result = run_block(
             &vec![
                 BlockNode::VariableDefinition {
-                    name: "x".into(),
+                    name: var("x", 0),
                     type_name: Type::Int,
                     value: ExpressionNode::Term(
                         TermNode::Integer(0)
-                    ),
-                },
+                    ).into(),
+                }.into(),
                 BlockNode::IfStatement {
                     condition: ExpressionNode::BinaryOperation {
                         verb: BinaryVerb::Compare,
-                        lhs: Box::new(ExpressionNode::Term(TermNode::Variable("x".into()))),
-                        rhs: Box::new(ExpressionNode::Term(TermNode::Integer(0))),
-                    },
+                        lhs: Box::new(ExpressionNode::Term(TermNode::Variable(var("x", 0))).into()),
+                        rhs: Box::new(ExpressionNode::Term(TermNode::Integer(0)).into()),
+                    }.into(),
                     block: vec![
                         BlockNode::Assignment {
-                            lhs: "x".into(),
-                            rhs: ExpressionNode::Term(TermNode::Integer(5)),
-                        }
+                            lhs: var("x", 0),
+                            rhs: ExpressionNode::Term(TermNode::Integer(5)).into(),
+                        }.into()
                     ],
-                },
+                    else_block: None,
+                }.into(),
                 BlockNode::Return(
-                    ExpressionNode::Term(TermNode::Variable("x".into()))
-                ),
+                    ExpressionNode::Term(TermNode::Variable(var("x", 0))).into()
+                ).into(),
             ],
             &mut scope,
         ).expect("Error with nested block");
 
-        assert_eq!(result, Some(Value::Int(5)))
+        assert_eq!(result, ControlFlow::Return(Value::Int(5)))
+    }
+
+    #[test]
+    fn test_else() {
+        let mut scope = Scope::new();
+
+        let result = run_block(
+            &vec![
+                BlockNode::IfStatement {
+                    condition: ExpressionNode::Term(TermNode::Boolean(false)).into(),
+                    block: vec![
+                        BlockNode::Return(
+                            ExpressionNode::Term(TermNode::Integer(1)).into()
+                        ).into(),
+                    ],
+                    else_block: Some(vec![
+                        BlockNode::IfStatement {
+                            condition: ExpressionNode::Term(TermNode::Boolean(false)).into(),
+                            block: vec![
+                                BlockNode::Return(
+                                    ExpressionNode::Term(TermNode::Integer(2)).into()
+                                ).into(),
+                            ],
+                            else_block: Some(vec![
+                                BlockNode::Return(
+                                    ExpressionNode::Term(TermNode::Integer(3)).into()
+                                ).into(),
+                            ]),
+                        }.into(),
+                    ]),
+                }.into(),
+                BlockNode::Return(
+                    ExpressionNode::Term(TermNode::Integer(0)).into()
+                ).into(),
+            ],
+            &mut scope,
+        ).expect("Error with else");
+
+        assert_eq!(result, ControlFlow::Return(Value::Int(3)))
+    }
+
+    #[test]
+    fn test_while_loop() {
+        let mut scope = Scope::new();
+
+        let result = run_block(
+            &vec![
+                BlockNode::VariableDefinition {
+                    name: var("x", 0),
+                    type_name: Type::Int,
+                    value: ExpressionNode::Term(TermNode::Integer(0)).into(),
+                }.into(),
+                BlockNode::WhileLoop {
+                    condition: ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Compare,
+                        lhs: Box::new(ExpressionNode::Term(TermNode::Variable(var("x", 0))).into()),
+                        rhs: Box::new(ExpressionNode::Term(TermNode::Integer(0)).into()),
+                    }.into(),
+                    block: vec![
+                        BlockNode::Assignment {
+                            lhs: var("x", 0),
+                            rhs: ExpressionNode::Term(TermNode::Integer(5)).into(),
+                        }.into()
+                    ],
+                }.into(),
+                BlockNode::Return(
+                    ExpressionNode::Term(TermNode::Variable(var("x", 0))).into()
+                ).into(),
+            ],
+            &mut scope,
+        ).expect("Error with while loop");
+
+        assert_eq!(result, ControlFlow::Return(Value::Int(5)))
+    }
+
+    #[test]
+    fn test_break_and_continue() {
+        let mut scope = Scope::new();
+
+        let result = run_block(
+            &vec![
+                BlockNode::VariableDefinition {
+                    name: var("x", 0),
+                    type_name: Type::Int,
+                    value: ExpressionNode::Term(TermNode::Integer(0)).into(),
+                }.into(),
+                BlockNode::WhileLoop {
+                    condition: ExpressionNode::Term(TermNode::Boolean(true)).into(),
+                    block: vec![
+                        BlockNode::Assignment {
+                            lhs: var("x", 0),
+                            rhs: ExpressionNode::BinaryOperation {
+                                verb: BinaryVerb::Plus,
+                                lhs: Box::new(ExpressionNode::Term(TermNode::Variable(var("x", 0))).into()),
+                                rhs: Box::new(ExpressionNode::Term(TermNode::Integer(1)).into()),
+                            }.into(),
+                        }.into(),
+                        BlockNode::IfStatement {
+                            condition: ExpressionNode::BinaryOperation {
+                                verb: BinaryVerb::Compare,
+                                lhs: Box::new(ExpressionNode::Term(TermNode::Variable(var("x", 0))).into()),
+                                rhs: Box::new(ExpressionNode::Term(TermNode::Integer(3)).into()),
+                            }.into(),
+                            block: vec![
+                                BlockNode::Break.into(),
+                            ],
+                            else_block: None,
+                        }.into(),
+                        BlockNode::Continue.into(),
+                        BlockNode::Return(
+                            ExpressionNode::Term(TermNode::Integer(-1)).into()
+                        ).into(),
+                    ],
+                }.into(),
+                BlockNode::Return(
+                    ExpressionNode::Term(TermNode::Variable(var("x", 0))).into()
+                ).into(),
+            ],
+            &mut scope,
+        ).expect("Error with break and continue");
+
+        assert_eq!(result, ControlFlow::Return(Value::Int(3)))
+    }
+
+    #[test]
+    fn test_return_from_loop() {
+        let mut scope = Scope::new();
+
+        let result = run_block(
+            &vec![
+                BlockNode::WhileLoop {
+                    condition: ExpressionNode::Term(TermNode::Boolean(true)).into(),
+                    block: vec![
+                        BlockNode::Return(
+                            ExpressionNode::Term(TermNode::Integer(7)).into()
+                        ).into(),
+                    ],
+                }.into(),
+                BlockNode::Return(
+                    ExpressionNode::Term(TermNode::Integer(0)).into()
+                ).into(),
+            ],
+            &mut scope,
+        ).expect("Error with return from loop");
+
+        assert_eq!(result, ControlFlow::Return(Value::Int(7)))
     }
 }
@@ -0,0 +1,223 @@
+use std::fmt::{Display, Formatter};
+use crate::parser::ast::{Ident, Span, Type};
+
+/// What went wrong during execution.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ErrorKind {
+    UndefinedVariable(Ident),
+    UndefinedFunction(Ident),
+    /// A value of the wrong type, for example in an assignment or as an argument.
+    TypeMismatch {
+        context: String,
+        expected: Type,
+        found: Type,
+    },
+    /// A binary operator applied to types it isn't defined for.
+    InvalidOperands {
+        operator: &'static str,
+        lhs: Type,
+        rhs: Type,
+    },
+    /// A unary operator applied to a type it isn't defined for.
+    InvalidOperand {
+        operator: &'static str,
+        operand: Type,
+    },
+    Arity {
+        function: Ident,
+        expected: usize,
+        found: usize,
+    },
+    /// A condition that isn't a bool.
+    InvalidCondition {
+        statement: &'static str,
+        found: Type,
+    },
+    DivisionByZero,
+    IntegerOverflow,
+    /// A character or part index outside of a string.
+    IndexOutOfRange {
+        index: i64,
+        length: usize,
+    },
+    /// A range that ends before it starts.
+    InvalidRange {
+        start: i64,
+        end: i64,
+    },
+    /// A negative number of repetitions.
+    NegativeCount(i64),
+    /// A string split at an empty separator.
+    EmptySeparator,
+    /// A string that isn't a valid value of the expected type.
+    Parse {
+        text: String,
+        expected: Type,
+    },
+    /// A function without a return type used as a value.
+    NoReturnValue(Ident),
+    /// A function with a return type that ended without returning.
+    MissingReturn(Ident),
+    /// A function without a return type that returned a value.
+    UnexpectedReturnValue(Ident),
+    BreakOutsideLoop,
+    /// An error reported by a native function.
+    Native(String),
+    /// Writing the output of the program failed.
+    Output(String),
+    /// More statements were run than allowed by the limits.
+    StatementLimit(u64),
+    /// More function calls were nested than allowed by the limits.
+    CallDepthLimit(usize),
+    /// The thread running the interpreter couldn't be started, for example
+    /// because there isn't enough memory for the stack the limits need.
+    Thread(String),
+    /// The program couldn't be compiled to bytecode.
+    Bytecode(String),
+    /// More bytes of strings were built than allowed by the limits.
+    StringLimit(usize),
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UndefinedVariable(name) => write!(f, "Undefined variable {name}"),
+            Self::UndefinedFunction(name) => write!(f, "Undefined function {name}"),
+            Self::TypeMismatch { context, expected, found } => {
+                write!(f, "Mismatched types for {context}: expected {expected}, found {found}")
+            }
+            Self::InvalidOperands { operator, lhs, rhs } => {
+                write!(f, "Operator {operator} can not be applied to {lhs} and {rhs}")
+            }
+            Self::InvalidOperand { operator, operand } => {
+                write!(f, "Unary operator {operator} can not be applied to {operand}")
+            }
+            Self::Arity { function, expected, found } => {
+                write!(f, "Function {function} expected {expected} arguments, got {found}")
+            }
+            Self::InvalidCondition { statement, found } => {
+                write!(f, "The condition of the {statement} must be bool, found {found}")
+            }
+            Self::DivisionByZero => write!(f, "Division by zero"),
+            Self::IntegerOverflow => write!(f, "Integer overflow"),
+            Self::IndexOutOfRange { index, length } => {
+                write!(f, "Index {index} is out of range for length {length}")
+            }
+            Self::InvalidRange { start, end } => write!(f, "Range starts at {start}, after its end {end}"),
+            Self::NegativeCount(count) => write!(f, "Can not repeat a string {count} times"),
+            Self::EmptySeparator => write!(f, "Can not split a string at an empty separator"),
+            Self::Parse { text, expected } => write!(f, "Can not parse \"{text}\" as {expected}"),
+            Self::NoReturnValue(name) => write!(f, "Function {name} does not return a value"),
+            Self::MissingReturn(name) => write!(f, "Expected return from function {name}"),
+            Self::UnexpectedReturnValue(name) => {
+                write!(f, "Function {name} returned a value, but it doesn't have a return type")
+            }
+            Self::BreakOutsideLoop => write!(f, "Break or continue outside of a loop"),
+            Self::Native(message) => write!(f, "{message}"),
+            Self::Output(error) => write!(f, "Could not write output: {error}"),
+            Self::StatementLimit(max) => write!(f, "Exceeded the limit of {max} statements"),
+            Self::CallDepthLimit(max) => write!(f, "Exceeded the maximum call depth of {max}"),
+            Self::Thread(error) => write!(f, "Could not start the interpreter: {error}"),
+            Self::Bytecode(error) => write!(f, "Could not compile the program to bytecode: {error}"),
+            Self::StringLimit(max) => write!(f, "Exceeded the limit of {max} bytes of strings"),
+        }
+    }
+}
+
+/// An error during execution, with where it happened.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RuntimeError {
+    pub kind: ErrorKind,
+    /// The innermost node that was being executed.
+    pub span: Option<Span>,
+    /// The KWLang functions that were running, innermost first.
+    pub backtrace: Vec<Ident>,
+}
+
+impl RuntimeError {
+    /// Attach the location of the node being executed, unless a more
+    /// precise location is already known.
+    pub fn at(mut self, span: Span) -> Self {
+        self.span.get_or_insert(span);
+        self
+    }
+
+    /// Record that the error happened in the given function.
+    ///
+    /// Errors without a location happened at the call itself, for example
+    /// because of the wrong number of arguments, so they belong to the caller.
+    pub fn in_function(mut self, name: &str) -> Self {
+        if self.span.is_some() {
+            self.backtrace.push(name.to_string());
+        }
+        self
+    }
+
+    /// Format the error with the location prefixed by the file name.
+    pub fn report(&self, file: &str) -> String {
+        match self.span {
+            Some(span) => format!("{file}:{span}: {}{}", self.kind, self.backtrace_text()),
+            None => format!("{file}: {}{}", self.kind, self.backtrace_text()),
+        }
+    }
+
+    fn backtrace_text(&self) -> String {
+        match self.backtrace.split_first() {
+            Some((innermost, callers)) => {
+                let callers: String = callers
+                    .iter()
+                    .map(|caller| format!(" called from {caller}"))
+                    .collect();
+
+                format!("\n    in {innermost}{callers}")
+            }
+            None => String::new(),
+        }
+    }
+}
+
+impl From<ErrorKind> for RuntimeError {
+    fn from(kind: ErrorKind) -> Self {
+        RuntimeError {
+            kind,
+            span: None,
+            backtrace: vec![],
+        }
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{span}: {}{}", self.kind, self.backtrace_text()),
+            None => write!(f, "{}{}", self.kind, self.backtrace_text()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::ast::Span;
+    use super::{ErrorKind, RuntimeError};
+
+    #[test]
+    fn test_report() {
+        let span = Span { start: 0, end: 1, line: 3, column: 12 };
+
+        let error = RuntimeError::from(ErrorKind::DivisionByZero)
+            .at(span)
+            .at(Span::default())
+            .in_function("double")
+            .in_function("main");
+
+        assert_eq!(
+            error.report("test.kw"),
+            "test.kw:3:12: Division by zero\n    in double called from main",
+        );
+
+        let error = RuntimeError::from(ErrorKind::UndefinedFunction("main".into()))
+            .in_function("main");
+
+        assert_eq!(error.report("test.kw"), "test.kw: Undefined function main");
+    }
+}
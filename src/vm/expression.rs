@@ -1,51 +1,80 @@
-use crate::parser::ast::{ExpressionNode, TermNode, BinaryVerb};
+use crate::parser::ast::{Expression, ExpressionNode, TermNode, BinaryVerb};
+use super::error::{ErrorKind, RuntimeError};
 use super::scope::Scope;
-use super::value::Value;
+use super::value::{binary_operation, logic_operand, unary_operation, Value};
 
-pub fn run_expression(expr: &ExpressionNode, scope: &Scope) -> Result<Value, String> {
+pub fn run_expression(expr: &Expression, scope: &mut Scope) -> Result<Value, RuntimeError> {
+    evaluate(&expr.node, scope).map_err(|error| error.at(expr.span))
+}
+
+fn evaluate(expr: &ExpressionNode, scope: &mut Scope) -> Result<Value, RuntimeError> {
     match expr {
+        ExpressionNode::BinaryOperation { verb: verb @ (BinaryVerb::And | BinaryVerb::Or), lhs, rhs } => {
+            // Only evaluate the right hand side if the left hand side doesn't decide the result
+            let short_circuit = *verb == BinaryVerb::Or;
+
+            if logic_operand(verb, "left", run_expression(lhs, scope)?)? == short_circuit {
+                Ok(Value::Bool(short_circuit))
+            } else {
+                Ok(Value::Bool(logic_operand(verb, "right", run_expression(rhs, scope)?)?))
+            }
+        },
         ExpressionNode::BinaryOperation { verb, lhs, rhs } => {
             let lhs = run_expression(lhs, scope)?;
             let rhs = run_expression(rhs, scope)?;
-            
-            if lhs.ast_type() != rhs.ast_type() {
-                Err(format!("Invalid operand types {} and {}", lhs.ast_type(), rhs.ast_type()))
-            } else {
-                match verb {
-                    BinaryVerb::Plus => {
-                        lhs + rhs
-                    },
-                    BinaryVerb::Minus => {
-                        lhs - rhs
-                    },
-                    BinaryVerb::Compare => {
-                        lhs.compare(&rhs)
-                    }
-                }
+            let result = binary_operation(verb, lhs, rhs)?;
+
+            if let Value::String(string) = &result {
+                scope.string(string)?;
             }
 
+            Ok(result)
+        },
+        ExpressionNode::Unary { op, operand } => {
+            let operand = run_expression(operand, scope)?;
+
+            Ok(unary_operation(op, operand)?)
+        },
+        ExpressionNode::Call { name, args } => {
+            Ok(run_call(name, args, scope)?
+                .ok_or(ErrorKind::NoReturnValue(name.clone()))?)
         },
         ExpressionNode::Term(term) => match term {
             TermNode::Variable(var) => {
-                scope.clone_variable(var).ok_or(format!("No such variable {var}"))
+                Ok(scope.get_variable(var)?.clone())
             },
             TermNode::Boolean(x) => Ok(Value::Bool(*x)),
             TermNode::Integer(x) => Ok(Value::Int(*x)),
-            TermNode::String(x) => Ok(Value::String(x.clone())),
+            TermNode::String(x) => Ok(Value::String(x.as_str().into())),
         }
     }
 }
 
+/// Evaluate the arguments in the current scope, and call the function.
+///
+/// Returns `None` if the function doesn't have a return value, which
+/// is only allowed when the call is used as a statement.
+pub fn run_call(name: &str, args: &[Expression], scope: &mut Scope) -> Result<Option<Value>, RuntimeError> {
+    let args = args
+        .iter()
+        .map(|arg| run_expression(arg, scope))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    scope.call_function(name, args)
+}
+
 #[cfg(test)]
 mod test {
-    use crate::parser::ast::{BinaryVerb, ExpressionNode, TermNode};
+    use crate::parser::ast::{BinaryVerb, Expression, ExpressionNode, FunctionNode, FunctionParam, TermNode, Type, BlockNode, UnaryVerb, Span, Variable, Program};
+    use crate::vm::function::Functions;
+    use crate::vm::limits::Limits;
     use crate::vm::value::Value;
     use super::run_expression;
     use super::super::scope::Scope;
 
     #[test]
     fn test_addition_int() {
-        let scope = Scope::new();
+        let mut scope = Scope::new();
 
         let result = run_expression(
             &ExpressionNode::BinaryOperation {
@@ -56,8 +85,8 @@ mod test {
                 rhs: ExpressionNode::Term(
                     TermNode::Integer(1)
                 ).into(),
-            },
-            &scope,
+            }.into(),
+            &mut scope,
         );
 
         assert_eq!(result, Ok(Value::Int(4)));
@@ -65,7 +94,7 @@ mod test {
 
     #[test]
     fn test_addition_string() {
-        let scope = Scope::new();
+        let mut scope = Scope::new();
         let result = run_expression(
             &ExpressionNode::BinaryOperation { 
                 verb: BinaryVerb::Plus, 
@@ -75,25 +104,289 @@ mod test {
                 rhs: ExpressionNode::Term(
                     TermNode::String("World".to_string())
                 ).into()
-            }, 
-            &scope
+            }.into(), 
+            &mut scope
         );
 
-        assert_eq!(result, Ok(Value::String("HelloWorld".to_string())));
+        assert_eq!(result, Ok(Value::String("HelloWorld".into())));
+    }
+
+    fn binary(verb: BinaryVerb, lhs: TermNode, rhs: TermNode) -> Expression {
+        ExpressionNode::BinaryOperation {
+            verb,
+            lhs: ExpressionNode::Term(lhs).into(),
+            rhs: ExpressionNode::Term(rhs).into(),
+        }.into()
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut scope = Scope::new();
+
+        let cases = [
+            (BinaryVerb::Multiply, 7, 3, 21),
+            (BinaryVerb::Divide, 7, 3, 2),
+            (BinaryVerb::Divide, -7, 2, -3),
+            (BinaryVerb::Modulo, 7, 3, 1),
+            (BinaryVerb::Modulo, -7, 3, -1),
+        ];
+
+        for (verb, lhs, rhs, expected) in cases {
+            let result = run_expression(
+                &binary(verb.clone(), TermNode::Integer(lhs), TermNode::Integer(rhs)),
+                &mut scope,
+            );
+
+            assert_eq!(result, Ok(Value::Int(expected)), "{lhs} {verb:?} {rhs}");
+        }
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        let mut scope = Scope::new();
+
+        for verb in [BinaryVerb::Divide, BinaryVerb::Modulo] {
+            run_expression(
+                &binary(verb, TermNode::Integer(1), TermNode::Integer(0)),
+                &mut scope,
+            ).expect_err("Division by zero should be an error");
+        }
+
+        run_expression(
+            &binary(BinaryVerb::Divide, TermNode::Integer(i64::MIN), TermNode::Integer(-1)),
+            &mut scope,
+        ).expect_err("Overflowing division should be an error");
+    }
+
+    #[test]
+    fn test_comparison() {
+        let mut scope = Scope::new();
+
+        let cases = [
+            (BinaryVerb::NotEqual, 1, 2, true),
+            (BinaryVerb::NotEqual, 2, 2, false),
+            (BinaryVerb::Less, 1, 2, true),
+            (BinaryVerb::Less, 2, 2, false),
+            (BinaryVerb::LessEqual, 2, 2, true),
+            (BinaryVerb::LessEqual, 3, 2, false),
+            (BinaryVerb::Greater, 3, 2, true),
+            (BinaryVerb::Greater, 2, 2, false),
+            (BinaryVerb::GreaterEqual, 2, 2, true),
+            (BinaryVerb::GreaterEqual, 1, 2, false),
+        ];
+
+        for (verb, lhs, rhs, expected) in cases {
+            let result = run_expression(
+                &binary(verb.clone(), TermNode::Integer(lhs), TermNode::Integer(rhs)),
+                &mut scope,
+            );
+
+            assert_eq!(result, Ok(Value::Bool(expected)), "{lhs} {verb:?} {rhs}");
+        }
+
+        run_expression(
+            &binary(BinaryVerb::Less, TermNode::String("a".into()), TermNode::String("b".into())),
+            &mut scope,
+        ).expect_err("Strings can not be ordered");
+
+        run_expression(
+            &binary(BinaryVerb::Multiply, TermNode::Boolean(true), TermNode::Boolean(true)),
+            &mut scope,
+        ).expect_err("Booleans can not be multiplied");
+    }
+
+    #[test]
+    fn test_bool_equality() {
+        let mut scope = Scope::new();
+
+        let cases = [
+            (false, false, true),
+            (false, true, false),
+            (true, false, false),
+            (true, true, true),
+        ];
+
+        for (lhs, rhs, expected) in cases {
+            let result = run_expression(
+                &binary(BinaryVerb::Compare, TermNode::Boolean(lhs), TermNode::Boolean(rhs)),
+                &mut scope,
+            );
+
+            assert_eq!(result, Ok(Value::Bool(expected)), "{lhs} == {rhs}");
+        }
+
+        run_expression(
+            &binary(BinaryVerb::Plus, TermNode::Boolean(true), TermNode::Boolean(true)),
+            &mut scope,
+        ).expect_err("Booleans can not be added");
+    }
+
+    #[test]
+    fn test_logic() {
+        let mut scope = Scope::new();
+
+        let cases = [
+            (BinaryVerb::And, false, false, false),
+            (BinaryVerb::And, true, false, false),
+            (BinaryVerb::And, true, true, true),
+            (BinaryVerb::Or, false, false, false),
+            (BinaryVerb::Or, false, true, true),
+            (BinaryVerb::Or, true, true, true),
+        ];
+
+        for (verb, lhs, rhs, expected) in cases {
+            let result = run_expression(
+                &binary(verb.clone(), TermNode::Boolean(lhs), TermNode::Boolean(rhs)),
+                &mut scope,
+            );
+
+            assert_eq!(result, Ok(Value::Bool(expected)), "{lhs} {verb:?} {rhs}");
+        }
+
+        let result = run_expression(
+            &ExpressionNode::Unary {
+                op: UnaryVerb::Not,
+                operand: ExpressionNode::Term(TermNode::Boolean(false)).into(),
+            }.into(),
+            &mut scope,
+        );
+
+        assert_eq!(result, Ok(Value::Bool(true)));
+
+        run_expression(
+            &ExpressionNode::Unary {
+                op: UnaryVerb::Not,
+                operand: ExpressionNode::Term(TermNode::Integer(1)).into(),
+            }.into(),
+            &mut scope,
+        ).expect_err("Integers can not be negated with !");
+
+        run_expression(
+            &binary(BinaryVerb::And, TermNode::Boolean(true), TermNode::Integer(1)),
+            &mut scope,
+        ).expect_err("Operands to && must be booleans");
+    }
+
+    #[test]
+    fn test_unary() {
+        let mut scope = Scope::new();
+
+        let unary = |op, term| -> Expression {
+            ExpressionNode::Unary {
+                op,
+                operand: ExpressionNode::Term(term).into(),
+            }.into()
+        };
+
+        assert_eq!(run_expression(&unary(UnaryVerb::Minus, TermNode::Integer(5)), &mut scope), Ok(Value::Int(-5)));
+        assert_eq!(run_expression(&unary(UnaryVerb::Plus, TermNode::Integer(5)), &mut scope), Ok(Value::Int(5)));
+
+        run_expression(&unary(UnaryVerb::Minus, TermNode::Integer(i64::MIN)), &mut scope)
+            .expect_err("Negating the smallest int should overflow");
+        run_expression(&unary(UnaryVerb::Minus, TermNode::Boolean(true)), &mut scope)
+            .expect_err("Booleans can not be negated with -");
+        run_expression(&unary(UnaryVerb::Plus, TermNode::String("".into())), &mut scope)
+            .expect_err("Unary + only applies to integers");
+    }
+
+    #[test]
+    fn test_short_circuit() {
+        let mut scope = Scope::new();
+
+        // The right hand side refers to an undefined variable, and would fail if evaluated
+        let result = run_expression(
+            &binary(BinaryVerb::And, TermNode::Boolean(false), TermNode::Variable("undefined".into())),
+            &mut scope,
+        );
+
+        assert_eq!(result, Ok(Value::Bool(false)));
+
+        let result = run_expression(
+            &binary(BinaryVerb::Or, TermNode::Boolean(true), TermNode::Variable("undefined".into())),
+            &mut scope,
+        );
+
+        assert_eq!(result, Ok(Value::Bool(true)));
+
+        run_expression(
+            &binary(BinaryVerb::And, TermNode::Boolean(true), TermNode::Variable("undefined".into())),
+            &mut scope,
+        ).expect_err("The right hand side should be evaluated when needed");
     }
 
     #[test]
     fn test_variable() {
         let mut scope = Scope::new();
-        scope.add_variable("x", Value::Int(9));
+        scope.add_variable(0, Value::Int(9));
 
         let result = run_expression(
             &ExpressionNode::Term(
-                TermNode::Variable("x".into())
-            ),
-            &scope,
+                TermNode::Variable(Variable { name: "x".into(), slot: Some(0) })
+            ).into(),
+            &mut scope,
         );
 
         assert_eq!(result, Ok(Value::Int(9)));
     }
+
+    #[test]
+    fn test_call() {
+        let double = FunctionNode {
+            name: "double".into(),
+            parameters: vec![
+                FunctionParam { name: "x".into(), type_name: Type::Int, span: Span::default() },
+            ],
+            return_type: Some(Type::Int),
+            block: vec![
+                BlockNode::Return(
+                    ExpressionNode::BinaryOperation {
+                        verb: BinaryVerb::Plus,
+                        lhs: ExpressionNode::Term(TermNode::Variable(Variable { name: "x".into(), slot: Some(0) })).into(),
+                        rhs: ExpressionNode::Term(TermNode::Variable(Variable { name: "x".into(), slot: Some(0) })).into(),
+                    }.into()
+                ).into(),
+            ],
+            doc: None,
+            span: Span::default(),
+        };
+        let nothing = FunctionNode {
+            name: "nothing".into(),
+            parameters: vec![],
+            return_type: None,
+            block: vec![],
+            doc: None,
+            span: Span::default(),
+        };
+        let program = Program { nodes: vec![double.into(), nothing.into()] };
+        let functions = Functions::new(&program, &[], &Limits::default());
+        let mut scope = Scope::with_limits(&functions, Limits::default(), std::io::sink());
+
+        let result = run_expression(
+            &ExpressionNode::Call {
+                name: "double".into(),
+                args: vec![
+                    ExpressionNode::Term(TermNode::Integer(4)).into(),
+                ],
+            }.into(),
+            &mut scope,
+        );
+
+        assert_eq!(result, Ok(Value::Int(8)));
+
+        run_expression(
+            &ExpressionNode::Call {
+                name: "nothing".into(),
+                args: vec![],
+            }.into(),
+            &mut scope,
+        ).expect_err("A function without return value can not be used as a value");
+
+        run_expression(
+            &ExpressionNode::Call {
+                name: "missing".into(),
+                args: vec![],
+            }.into(),
+            &mut scope,
+        ).expect_err("Calling an undefined function should fail");
+    }
 }
@@ -1,44 +1,120 @@
-use crate::parser::ast::FunctionNode;
-use crate::vm::block::run_block;
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::parser::ast::{FunctionNode, Program, Type};
+use crate::stack;
+use crate::vm::block::{ControlFlow, run_block};
+use super::error::{ErrorKind, RuntimeError};
+use super::limits::Limits;
+use super::native::NativeFunction;
 use super::scope::Scope;
 use super::value::Value;
 
-pub fn run_function(function: &FunctionNode, scope: &mut Scope, args: Vec<Value>) -> Result<Option<Value>, String> {
-    if function.parameters.len() != args.len() {
-        Err(format!("Function {} expected {} arguments, got {}", function.name, function.parameters.len(), args.len()))
-    } else {
-        // Handle parameters
-        for (param, value) in function.parameters.iter().zip(args) {
-            if value.ast_type() != param.type_name {
-                Err(format!(
-                    "Parameter {} to function {} should be of type {}, but is {}",
-                    param.name,
-                    function.name,
-                    param.type_name,
-                    value,
-                ))?
-            } else {
-                scope.add_variable(&param.name, value);
-            }
+/// A function defined in the program or by the host.
+#[derive(Debug, Clone)]
+pub enum Function {
+    Defined(Arc<FunctionNode>),
+    Native(NativeFunction),
+}
+
+/// The functions that can be called, built once for a program and shared
+/// by all of its calls.
+#[derive(Debug, Default, Clone)]
+pub struct Functions {
+    functions: HashMap<String, Function>,
+    /// The most levels of nesting the calls reach, see [`stack::call_levels`].
+    levels: usize,
+}
+
+impl Functions {
+    pub fn new(program: &Program, natives: &[NativeFunction], limits: &Limits) -> Self {
+        let natives = natives.iter().map(|native| (native.name().to_string(), Function::Native(native.clone())));
+        let defined = program.nodes.iter().map(|function| (function.name.clone(), Function::Defined(Arc::clone(function))));
+
+        Functions {
+            functions: natives.chain(defined).collect(),
+            levels: stack::call_levels(program, limits.max_call_depth),
         }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Function> {
+        self.functions.get(name)
+    }
+
+    pub fn levels(&self) -> usize {
+        self.levels
+    }
+}
 
-        // Handle the return value
-        if let Some(value) = scope.subscope(|s| run_block(&function.block, s))? {
-            if let Some(return_type) = &function.return_type {
-                if value.ast_type() == *return_type {
-                    Ok(Some(value))
-                } else {
-                    Err(format!("Function {} returned {}, but the return type is {}", function.name, value, return_type))
-                }
-            } else {
-                Err(format!("Function {} returned {}, but it doesn't have a return value", function.name, value))
-            }
-        } else if function.return_type.is_none() {
-            Ok(None)
+pub fn run_function(function: &FunctionNode, scope: &mut Scope, args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    check_arity(&function.name, function.parameters.len(), args.len())?;
+
+    // The parameters use the first slots
+    for (slot, (param, value)) in function.parameters.iter().zip(args).enumerate() {
+        if value.ast_type() != param.type_name {
+            Err(ErrorKind::TypeMismatch {
+                context: format!("parameter {} of function {}", param.name, function.name),
+                expected: param.type_name,
+                found: value.ast_type(),
+            })?
         } else {
-            Err(format!("Expected return from function {}", function.name))
+            scope.add_variable(slot, value);
         }
     }
+
+    let result = match run_block(&function.block, scope)? {
+        ControlFlow::Return(value) => check_return(&function.name, function.return_type, Some(value)),
+        ControlFlow::Break | ControlFlow::Continue => Err(ErrorKind::BreakOutsideLoop),
+        ControlFlow::Next => check_return(&function.name, function.return_type, None),
+    };
+
+    // These errors are about the function as a whole, not the call
+    result.map_err(|kind| RuntimeError::from(kind).at(function.span))
+}
+
+/// Call a native function, with the same checks as for functions in the program.
+///
+/// Errors happen at the call, since the function has no location in the program.
+pub fn run_native(native: &NativeFunction, args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    let signature = &native.signature;
+
+    check_arity(&signature.name, signature.parameters.len(), args.len())?;
+
+    for (index, (expected, value)) in signature.parameters.iter().zip(&args).enumerate() {
+        if value.ast_type() != *expected {
+            Err(ErrorKind::TypeMismatch {
+                context: format!("argument {} of function {}", index + 1, signature.name),
+                expected: *expected,
+                found: value.ast_type(),
+            })?
+        }
+    }
+
+    let result = native.call(&args)?;
+
+    Ok(check_return(&signature.name, signature.return_type, result)?)
+}
+
+fn check_arity(function: &str, expected: usize, found: usize) -> Result<(), ErrorKind> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(ErrorKind::Arity { function: function.to_string(), expected, found })
+    }
+}
+
+/// Check that a function returned a value of its return type, or nothing if it doesn't have one.
+fn check_return(function: &str, return_type: Option<Type>, value: Option<Value>) -> Result<Option<Value>, ErrorKind> {
+    match (return_type, value) {
+        (Some(return_type), Some(value)) if value.ast_type() == return_type => Ok(Some(value)),
+        (Some(return_type), Some(value)) => Err(ErrorKind::TypeMismatch {
+            context: format!("return value of function {function}"),
+            expected: return_type,
+            found: value.ast_type(),
+        }),
+        (Some(_), None) => Err(ErrorKind::MissingReturn(function.to_string())),
+        (None, Some(_)) => Err(ErrorKind::UnexpectedReturnValue(function.to_string())),
+        (None, None) => Ok(None),
+    }
 }
 
 #[cfg(test)]
@@ -60,6 +136,8 @@ mod test {
                 parameters: vec![],
                 return_type: None,
                 block: vec![],
+                doc: None,
+                span: Span::default(),
             },
             &mut scope,
             vec![],
@@ -74,10 +152,12 @@ mod test {
             &FunctionNode {
                 name: "test".into(),
                 parameters: vec![
-                    FunctionParam { name: "x".into(), type_name: Type::Int },
+                    FunctionParam { name: "x".into(), type_name: Type::Int, span: Span::default() },
                 ],
                 return_type: None,
                 block: vec![],
+                doc: None,
+                span: Span::default(),
             },
             &mut scope,
             vec![
@@ -89,11 +169,13 @@ mod test {
             &FunctionNode {
                 name: "test".into(),
                 parameters: vec![
-                    FunctionParam { name: "x".into(), type_name: Type::Int },
-                    FunctionParam { name: "y".into(), type_name: Type::String },
+                    FunctionParam { name: "x".into(), type_name: Type::Int, span: Span::default() },
+                    FunctionParam { name: "y".into(), type_name: Type::String, span: Span::default() },
                 ],
                 return_type: None,
                 block: vec![],
+                doc: None,
+                span: Span::default(),
             },
             &mut scope,
             vec![
@@ -106,10 +188,12 @@ mod test {
             &FunctionNode {
                 name: "test".into(),
                 parameters: vec![
-                    FunctionParam { name: "x".into(), type_name: Type::Int },
+                    FunctionParam { name: "x".into(), type_name: Type::Int, span: Span::default() },
                 ],
                 return_type: None,
                 block: vec![],
+                doc: None,
+                span: Span::default(),
             },
             &mut scope,
             vec![
@@ -122,11 +206,13 @@ mod test {
             &FunctionNode {
                 name: "test".into(),
                 parameters: vec![
-                    FunctionParam { name: "x".into(), type_name: Type::Int },
-                    FunctionParam { name: "y".into(), type_name: Type::String },
+                    FunctionParam { name: "x".into(), type_name: Type::Int, span: Span::default() },
+                    FunctionParam { name: "y".into(), type_name: Type::String, span: Span::default() },
                 ],
                 return_type: None,
                 block: vec![],
+                doc: None,
+                span: Span::default(),
             },
             &mut scope,
             vec![
@@ -138,10 +224,12 @@ mod test {
             &FunctionNode {
                 name: "test".into(),
                 parameters: vec![
-                    FunctionParam { name: "x".into(), type_name: Type::Int },
+                    FunctionParam { name: "x".into(), type_name: Type::Int, span: Span::default() },
                 ],
                 return_type: None,
                 block: vec![],
+                doc: None,
+                span: Span::default(),
             },
             &mut scope,
             vec![
@@ -161,9 +249,11 @@ mod test {
                 return_type: Some(Type::Bool),
                 block: vec![
                     BlockNode::Return(
-                        ExpressionNode::Term(TermNode::Boolean(true))
-                    )
+                        ExpressionNode::Term(TermNode::Boolean(true)).into()
+                    ).into()
                 ],
+                doc: None,
+                span: Span::default(),
             },
             &mut scope,
             vec![],
@@ -176,9 +266,11 @@ mod test {
                 return_type: Some(Type::String),
                 block: vec![
                     BlockNode::Return(
-                        ExpressionNode::Term(TermNode::Boolean(true))
-                    )
+                        ExpressionNode::Term(TermNode::Boolean(true)).into()
+                    ).into()
                 ],
+                doc: None,
+                span: Span::default(),
             },
             &mut scope,
             vec![],
@@ -191,9 +283,11 @@ mod test {
                 return_type: None,
                 block: vec![
                     BlockNode::Return(
-                        ExpressionNode::Term(TermNode::Boolean(true))
-                    )
+                        ExpressionNode::Term(TermNode::Boolean(true)).into()
+                    ).into()
                 ],
+                doc: None,
+                span: Span::default(),
             },
             &mut scope,
             vec![],
@@ -205,6 +299,8 @@ mod test {
                 parameters: vec![],
                 return_type: Some(Type::Int),
                 block: vec![],
+                doc: None,
+                span: Span::default(),
             },
             &mut scope,
             vec![],
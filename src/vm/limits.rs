@@ -0,0 +1,95 @@
+use crate::vm::error::ErrorKind;
+
+/// Bounds on the resources a program may use, so that runaway programs stop
+/// with an error instead of running forever or overflowing the stack.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Limits {
+    /// The number of statements that may be run, or `None` for no limit.
+    ///
+    /// Every check of a loop condition also counts as a statement, so that
+    /// empty loops run out as well.
+    pub max_statements: Option<u64>,
+    /// The number of function calls that may be running at the same time,
+    /// including `main`.
+    ///
+    /// Every call uses the stack of the interpreter, so this is always limited.
+    pub max_call_depth: usize,
+    /// How deeply brackets, operators and `else` branches may be nested in
+    /// the source, checked when it is loaded, see [`crate::Interpreter::load`].
+    ///
+    /// The parser, the analysis and the compilers use the stack for each
+    /// level, so this is always limited.
+    pub max_nesting_depth: usize,
+    /// The total number of bytes of the strings built while running, or
+    /// `None` for no limit. String literals are part of the program and
+    /// don't count.
+    pub max_string_bytes: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_statements: None,
+            max_call_depth: 1000,
+            max_nesting_depth: 1000,
+            max_string_bytes: Some(256 * 1024 * 1024),
+        }
+    }
+}
+
+/// The resources used so far, checked against the limits.
+#[derive(Debug, Default)]
+pub struct Usage {
+    statements: u64,
+    string_bytes: usize,
+}
+
+impl Usage {
+    pub fn statement(&mut self, limits: &Limits) -> Result<(), ErrorKind> {
+        self.statements += 1;
+
+        match limits.max_statements {
+            Some(max) if self.statements > max => Err(ErrorKind::StatementLimit(max)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn string(&mut self, limits: &Limits, bytes: usize) -> Result<(), ErrorKind> {
+        self.string_bytes = self.string_bytes.saturating_add(bytes);
+
+        match limits.max_string_bytes {
+            Some(max) if self.string_bytes > max => Err(ErrorKind::StringLimit(max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::vm::error::ErrorKind;
+    use super::{Limits, Usage};
+
+    #[test]
+    fn test_usage() {
+        let limits = Limits {
+            max_statements: Some(2),
+            max_call_depth: 1,
+            max_nesting_depth: 1,
+            max_string_bytes: Some(10),
+        };
+        let mut usage = Usage::default();
+
+        assert_eq!(usage.statement(&limits), Ok(()));
+        assert_eq!(usage.statement(&limits), Ok(()));
+        assert_eq!(usage.statement(&limits), Err(ErrorKind::StatementLimit(2)));
+
+        assert_eq!(usage.string(&limits, 10), Ok(()));
+        assert_eq!(usage.string(&limits, 1), Err(ErrorKind::StringLimit(10)));
+
+        let mut usage = Usage::default();
+
+        for _ in 0..100 {
+            assert_eq!(usage.statement(&Limits::default()), Ok(()));
+        }
+    }
+}
@@ -1,27 +1,77 @@
 mod scope;
-mod function;
-mod value;
+pub(crate) mod function;
+pub mod value;
 mod expression;
 mod block;
+pub mod error;
+pub(crate) mod limits;
+pub mod native;
 
 #[cfg(test)]
 mod test;
 
+use std::io::Write;
+#[cfg(test)]
 use crate::parser::ast::Program;
+use crate::parser::ast::Type;
+use crate::stack::{self, RUN_STACK_PER_LEVEL};
+use self::error::{ErrorKind, RuntimeError};
+pub use self::limits::Limits;
+use self::function::Functions;
 use self::scope::Scope;
 use self::value::Value;
 
-pub fn run(program: &Program) -> Result<i32, String> {
-    let mut scope = Scope::new();
+/// Run the main function of a resolved program, see [`crate::resolver::resolve`].
+///
+/// Variables that couldn't be resolved are reported as undefined when they are used.
+#[cfg(test)]
+pub fn run(program: &Program) -> Result<i64, RuntimeError> {
+    run_with_limits(program, Limits::default())
+}
 
-    for function in &program.nodes {
-        scope.add_function(function)
-    }
+/// Run the main function of a resolved program, stopping with an error
+/// when it exceeds one of the limits.
+#[cfg(test)]
+pub fn run_with_limits(program: &Program, limits: Limits) -> Result<i64, RuntimeError> {
+    run_with_output(program, limits, &mut std::io::stdout())
+}
 
-    match scope.call_function("main", vec![]) {
-        Ok(Some(Value::Int(return_code))) => Ok(return_code),
-        Ok(Some(value)) => Err(format!("Illegal non-integer return value from main: {value}")),
-        Ok(None) => Err("Expected integer return value from main".into()),
-        Err(error) => Err(error),
+/// Run the main function of a resolved program, with the builtins writing to the output.
+#[cfg(test)]
+pub fn run_with_output(program: &Program, limits: Limits, output: &mut (dyn Write + Send)) -> Result<i64, RuntimeError> {
+    let functions = Functions::new(program, &crate::strings::natives(), &limits);
+
+    Ok(return_code(call(&functions, "main", vec![], limits, output)?)?)
+}
+
+/// The exit code of a program, from the return value of its main function.
+pub(crate) fn return_code(value: Option<Value>) -> Result<i64, ErrorKind> {
+    match value {
+        Some(Value::Int(return_code)) => Ok(return_code),
+        Some(value) => Err(ErrorKind::TypeMismatch {
+            context: "return value of main".into(),
+            expected: Type::Int,
+            found: value.ast_type(),
+        }),
+        None => Err(ErrorKind::NoReturnValue("main".into())),
     }
 }
+
+/// Call a function of a resolved program with the given arguments. The
+/// program can call the functions of the table, and the builtins write to
+/// the output.
+///
+/// Returns `None` if the function doesn't have a return value. The function
+/// runs on its own thread, with a stack large enough for the calls up to the
+/// maximum call depth, see [`Functions::levels`].
+pub fn call(
+    functions: &Functions,
+    name: &str,
+    args: Vec<Value>,
+    limits: Limits,
+    output: &mut (dyn Write + Send),
+) -> Result<Option<Value>, RuntimeError> {
+    stack::run_nested(functions.levels(), RUN_STACK_PER_LEVEL, || {
+        Scope::with_limits(functions, limits, output).call_function(name, args)
+    }).map_err(|error| ErrorKind::Thread(error.to_string()))?
+}
@@ -0,0 +1,67 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use crate::parser::ast::{Ident, Signature, Type};
+use super::error::ErrorKind;
+use super::value::Value;
+
+/// The Rust code of a native function.
+///
+/// It is only called with arguments of the declared parameter types, and
+/// returns `None` if the function doesn't have a return type.
+pub type NativeFn = dyn Fn(&[Value]) -> Result<Option<Value>, ErrorKind> + Send + Sync;
+
+/// A function implemented by the host application, which KWLang code can
+/// call like any other function.
+#[derive(Clone)]
+pub struct NativeFunction {
+    pub signature: Signature,
+    function: Arc<NativeFn>,
+    /// An estimate of the bytes of the string the function builds from the
+    /// arguments, see [`NativeFunction::with_string_bytes`].
+    string_bytes: fn(&[Value]) -> usize,
+}
+
+impl NativeFunction {
+    pub fn new<F>(name: impl Into<Ident>, parameters: Vec<Type>, return_type: Option<Type>, function: F) -> Self
+        where F: Fn(&[Value]) -> Result<Option<Value>, ErrorKind> + Send + Sync + 'static
+    {
+        NativeFunction {
+            signature: Signature {
+                name: name.into(),
+                parameters,
+                return_type,
+            },
+            function: Arc::new(function),
+            string_bytes: |_| 0,
+        }
+    }
+
+    /// Check the strings the function builds against the limits, with an
+    /// estimate of their bytes from the arguments. The estimate is charged
+    /// before running the function, so that it can't grow past the limit.
+    pub fn with_string_bytes(mut self, string_bytes: fn(&[Value]) -> usize) -> Self {
+        self.string_bytes = string_bytes;
+        self
+    }
+
+    pub fn string_bytes(&self, args: &[Value]) -> usize {
+        (self.string_bytes)(args)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.signature.name
+    }
+
+    /// Run the Rust code, without checking the types.
+    pub(super) fn call(&self, args: &[Value]) -> Result<Option<Value>, ErrorKind> {
+        (self.function)(args)
+    }
+}
+
+impl Debug for NativeFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NativeFunction")
+            .field("signature", &self.signature)
+            .finish_non_exhaustive()
+    }
+}
@@ -1,179 +1,197 @@
-use std::collections::HashMap;
-use crate::parser::ast::FunctionNode;
-use crate::vm::function::run_function;
+use std::io::Write;
+use crate::builtin::Builtin;
+use crate::parser::ast::Variable;
+use crate::vm::error::{ErrorKind, RuntimeError};
+use crate::vm::function::{run_function, run_native, Function, Functions};
+use crate::vm::limits::{Limits, Usage};
 use super::value::Value;
 
-struct ScopeLayer {
-    variables: HashMap<String, Value>,
+/// The variables of a single function call, indexed by the slots given by the resolver.
+struct Frame {
+    slots: Vec<Option<Value>>,
 }
 
-impl ScopeLayer {
+impl Frame {
     fn new() -> Self {
-        ScopeLayer {
-            variables: HashMap::new(),
+        Frame {
+            slots: vec![],
         }
     }
 }
 
-pub struct Scope {
-    layers: Vec<ScopeLayer>,
-    functions: HashMap<String, FunctionNode>,
+pub struct Scope<'a> {
+    /// The frame of the running function is at the top. A function can only
+    /// see the variables in its own frame.
+    frames: Vec<Frame>,
+    functions: &'a Functions,
+    limits: Limits,
+    usage: Usage,
+    /// Where the builtins write to.
+    output: Box<dyn Write + 'a>,
 }
 
-impl Scope {
+impl<'a> Scope<'a> {
+    /// A scope without functions besides the builtins.
+    #[cfg(test)]
     pub fn new() -> Self {
+        static FUNCTIONS: std::sync::LazyLock<Functions> = std::sync::LazyLock::new(Functions::default);
+
+        Self::with_limits(&FUNCTIONS, Limits::default(), std::io::sink())
+    }
+
+    pub fn with_limits(functions: &'a Functions, limits: Limits, output: impl Write + 'a) -> Self {
         Scope {
-            layers: vec![ScopeLayer::new()],
-            functions: HashMap::new(),
+            frames: vec![Frame::new()],
+            functions,
+            limits,
+            usage: Usage::default(),
+            output: Box::new(output),
         }
     }
 
-    fn push_layer(&mut self) {
-        self.layers.push(ScopeLayer::new())
+    fn frame(&self) -> &Frame {
+        self.frames.last().expect("Scope has no frames")
     }
 
-    fn pop_layer(&mut self) {
-        if self.layers.len() > 1 {
-            self.layers.pop();
-        } else {
-            panic!("Tried to remove last layer of scope. This should not be possible.")
-        }
+    fn frame_mut(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("Scope has no frames")
     }
 
-    pub fn subscope<T, F>(&mut self, f: F) -> T
-        where F: FnOnce(&mut Scope) -> T
+    /// Run `f` in a new frame, which can't see the variables of the caller.
+    fn call_frame<T, F>(&mut self, f: F) -> T
+        where F: FnOnce(&mut Scope<'a>) -> T
     {
-        self.push_layer();
+        self.frames.push(Frame::new());
         let result = f(self);
-        self.pop_layer();
+        self.frames.pop();
         result
     }
 
-    pub fn add_variable(&mut self, name: impl ToString, value: Value) {
-        self.layers.last_mut().expect("Scope has no layers").variables.insert(name.to_string(), value);
+    /// Count a statement against the limits.
+    pub fn statement(&mut self) -> Result<(), ErrorKind> {
+        self.usage.statement(&self.limits)
     }
 
-    pub fn set_variable(&mut self, name: impl ToString, value: Value) -> Result<(), String> {
-        let key = name.to_string();
-
-        self.layers
-            .iter_mut()
-            .rev()
-            .find_map(|layer| layer.variables.get_mut(&key))
-            .map(|v| *v = value)
-            .ok_or(format!("Could not find variable {key}"))
+    /// Count a newly built string against the limits.
+    pub fn string(&mut self, value: &str) -> Result<(), ErrorKind> {
+        self.usage.string(&self.limits, value.len())
     }
 
-    pub fn get_variable(&self, name: &str) -> Option<&Value> {
-        self.layers
-            .iter()
-            .rev()
-            .find_map(|layer| layer.variables.get(name))
+    pub fn add_variable(&mut self, slot: usize, value: Value) {
+        let slots = &mut self.frame_mut().slots;
+
+        if slots.len() <= slot {
+            slots.resize(slot + 1, None);
+        }
+
+        slots[slot] = Some(value);
     }
 
-    pub fn clone_variable(&self, name: &str) -> Option<Value> {
-        self.get_variable(name).map(Clone::clone)
+    /// Set the value of a variable that has already been defined.
+    pub fn set_variable(&mut self, variable: &Variable, value: Value) -> Result<(), ErrorKind> {
+        let undefined = || ErrorKind::UndefinedVariable(variable.name.clone());
+        let slot = variable.slot.ok_or_else(undefined)?;
+
+        match self.frame_mut().slots.get_mut(slot) {
+            Some(Some(current)) => {
+                *current = value;
+                Ok(())
+            }
+            _ => Err(undefined()),
+        }
     }
 
-    pub fn add_function(&mut self, function: &FunctionNode) {
-        self.functions.insert(function.name.clone(), function.clone());
+    pub fn get_variable(&self, variable: &Variable) -> Result<&Value, ErrorKind> {
+        variable.slot
+            .and_then(|slot| self.frame().slots.get(slot))
+            .and_then(Option::as_ref)
+            .ok_or_else(|| ErrorKind::UndefinedVariable(variable.name.clone()))
     }
 
-    pub fn call_function(&mut self, name: &str, args: Vec<Value>) -> Result<Option<Value>, String> {
-        self.subscope(|scope| {
-            scope.functions
-                .get(name)
-                .cloned()
-                .ok_or(format!("No function '{name}'"))
-                .and_then(|f| {
-                    run_function(&f, scope, args)
-                })
-        })
+    pub fn call_function(&mut self, name: &str, args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+        let Some(function) = self.functions.get(name) else {
+            let builtin = Builtin::from_name(name).ok_or(ErrorKind::UndefinedFunction(name.to_string()))?;
+
+            // Charged before building the string, so that it can't grow past the limit
+            self.usage.string(&self.limits, builtin.string_bytes(&args))?;
+
+            return Ok(builtin.call(&args, &mut self.output)?);
+        };
+
+        match function {
+            Function::Defined(function) => {
+                // The first frame doesn't belong to a function
+                if self.frames.len() > self.limits.max_call_depth {
+                    Err(ErrorKind::CallDepthLimit(self.limits.max_call_depth))?
+                }
+
+                self.call_frame(|scope| run_function(function, scope, args))
+            }
+            // Native functions don't have a frame, and can't call other functions
+            Function::Native(native) => {
+                // Charged before building the string, so that it can't grow past the limit
+                self.usage.string(&self.limits, native.string_bytes(&args))?;
+
+                run_native(native, args)
+            }
+        }.map_err(|error| error.in_function(name))
     }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::parser::ast::Variable;
     use crate::vm::scope::Scope;
     use crate::vm::value::Value;
 
+    fn var(name: &str, slot: usize) -> Variable {
+        Variable { name: name.into(), slot: Some(slot) }
+    }
+
     #[test]
-    fn test_single_scope() {
+    fn test_slots() {
         let mut scope = Scope::new();
 
-        scope.add_variable("x", Value::Int(5));
-
-        assert_eq!(
-            scope.get_variable("x").expect("Could not find x"),
-            &Value::Int(5),
-        );
+        scope.add_variable(0, Value::Int(5));
 
-        scope.add_variable("y", Value::Bool(true));
+        assert_eq!(scope.get_variable(&var("x", 0)), Ok(&Value::Int(5)));
 
-        assert_eq!(
-            scope.get_variable("y").expect("Could not find y"),
-            &Value::Bool(true),
-        );
+        scope.add_variable(2, Value::Bool(true));
 
-        assert_eq!(
-            scope.get_variable("x").expect("Could not find x"),
-            &Value::Int(5),
-        );
+        assert_eq!(scope.get_variable(&var("y", 2)), Ok(&Value::Bool(true)));
+        assert_eq!(scope.get_variable(&var("x", 0)), Ok(&Value::Int(5)));
 
-        scope.add_variable("x", Value::Int(0));
-        assert_eq!(
-            scope.get_variable("x").expect("Could not find x"),
-            &Value::Int(0),
-        );
+        scope.set_variable(&var("x", 0), Value::Int(0)).unwrap();
+        assert_eq!(scope.get_variable(&var("x", 0)), Ok(&Value::Int(0)));
     }
 
     #[test]
-    fn test_read_parent() {
-        let mut parent = Scope::new();
-        parent.add_variable("x", Value::Int(5));
-
-        parent.subscope(|s| {
-            s.add_variable("y", Value::Int(0));
-
-            assert_eq!(
-                s.get_variable("x").expect("Could not find x in child"),
-                &Value::Int(5),
-            );
-
-            assert_eq!(
-                s.get_variable("y").expect("Could not find y in child"),
-                &Value::Int(0),
-            );
-        });
+    fn test_undefined() {
+        let mut scope = Scope::new();
 
-        assert_eq!(
-            parent.get_variable("x").expect("Could not find x in parent"),
-            &Value::Int(5),
-        );
+        scope.add_variable(1, Value::Int(5));
 
-        assert_eq!(
-            parent.get_variable("y"),
-            None,
-        );
+        assert!(scope.get_variable(&var("x", 0)).is_err());
+        assert!(scope.get_variable(&var("x", 2)).is_err());
+        assert!(scope.get_variable(&"x".into()).is_err());
+        assert!(scope.set_variable(&var("x", 0), Value::Int(0)).is_err());
     }
 
     #[test]
-    fn test_write_parent() {
-        let mut parent = Scope::new();
-        parent.add_variable("x", Value::Int(5));
+    fn test_call_frame() {
+        let mut scope = Scope::new();
 
-        parent.subscope(|s| {
-            s.set_variable("x", Value::Int(0)).unwrap();
+        scope.call_frame(|caller| {
+            caller.add_variable(0, Value::Int(5));
 
-            assert_eq!(
-                s.get_variable("x").expect("Could not find x in child"),
-                &Value::Int(0),
-            );
-        });
+            caller.call_frame(|callee| {
+                assert!(callee.get_variable(&var("x", 0)).is_err());
+                assert!(callee.set_variable(&var("x", 0), Value::Int(0)).is_err());
 
-        assert_eq!(
-            parent.get_variable("x").expect("Could not find x in parent"),
-            &Value::Int(0),
-        );
+                callee.add_variable(0, Value::Int(9));
+            });
+
+            assert_eq!(caller.get_variable(&var("x", 0)), Ok(&Value::Int(5)));
+        });
     }
 }
@@ -1,5 +1,40 @@
 use crate::parser::ast::*;
-use super::super::run;
+use crate::parser::parse;
+use crate::bytecode;
+use crate::resolver::resolve;
+use super::error::{ErrorKind, RuntimeError};
+use super::Limits;
+
+/// Resolve the program, and run it with both the tree-walker and the bytecode VM,
+/// checking that they agree.
+///
+/// Programs with name errors still run on the tree-walker, which reports
+/// the names that couldn't be resolved when they are used.
+fn run(program: &Program) -> Result<i64, RuntimeError> {
+    run_with_output(program).0
+}
+
+/// Like [`run`], but also return what the program printed.
+fn run_with_output(program: &Program) -> (Result<i64, RuntimeError>, String) {
+    let mut resolved = program.clone();
+    let resolution = resolve(&mut resolved);
+
+    let mut output = vec![];
+    let result = super::run_with_output(&resolved, Limits::default(), &mut output);
+    let output = String::from_utf8(output).expect("The output should be UTF-8");
+
+    if resolution.is_ok() {
+        let module = bytecode::compile(&resolved).expect("Could not compile program to bytecode");
+        let mut bytecode_output = vec![];
+        let bytecode_result = bytecode::execute_with_output(&module, Limits::default(), &mut bytecode_output);
+
+        // The errors are compared with their locations and backtraces
+        assert_eq!(bytecode_result, result);
+        assert_eq!(String::from_utf8(bytecode_output).expect("The output should be UTF-8"), output);
+    }
+
+    (result, output)
+}
 
 #[test]
 fn test_smallest_program() {
@@ -14,10 +49,12 @@ fn test_smallest_program() {
                         BlockNode::Return(
                             ExpressionNode::Term(
                                 TermNode::Integer(5)
-                            )
-                        )
+                            ).into()
+                        ).into()
                     ],
-                }
+                    doc: None,
+                    span: Span::default(),
+                }.into()
             ]
         }
     );
@@ -41,7 +78,9 @@ fn test_missing_main() {
                     parameters: vec![],
                     return_type: None,
                     block: vec![],
-                }
+                    doc: None,
+                    span: Span::default(),
+                }.into()
             ]
         }
     ).expect_err("The main function should be required to have the name 'main'");
@@ -60,10 +99,12 @@ fn test_main_return_type() {
                         BlockNode::Return(
                             ExpressionNode::Term(
                                 TermNode::String("test".into())
-                            )
-                        )
+                            ).into()
+                        ).into()
                     ],
-                }
+                    doc: None,
+                    span: Span::default(),
+                }.into()
             ]
         }
     ).expect_err("The main function should be required to return int");
@@ -76,8 +117,568 @@ fn test_main_return_type() {
                     parameters: vec![],
                     return_type: None,
                     block: vec![],
-                }
+                    doc: None,
+                    span: Span::default(),
+                }.into()
             ]
         }
     ).expect_err("The main function should be required to return int");
 }
+
+#[test]
+fn test_function_call() {
+    let program = parse(include_str!("../../tests/function_call.kw")).expect("Could not parse program");
+
+    assert_eq!(run(&program), Ok(10))
+}
+
+#[test]
+fn test_expression() {
+    let program = parse(include_str!("../../tests/expression.kw")).expect("Could not parse program");
+
+    assert_eq!(run(&program), Ok(-1))
+}
+
+#[test]
+fn test_left_associativity() {
+    let program = parse("
+        function main() -> int {
+            if (10 - 4 - 3 == 3) {
+                return 1 - 2 + 3;
+            }
+
+            return 0;
+        }
+    ").expect("Could not parse program");
+
+    assert_eq!(run(&program), Ok(2))
+}
+
+#[test]
+fn test_while_loop() {
+    let program = parse(include_str!("../../tests/while_loop.kw")).expect("Could not parse program");
+
+    assert_eq!(run(&program), Ok(120 + 4947))
+}
+
+#[test]
+fn test_if_else() {
+    let program = parse(include_str!("../../tests/if_else.kw")).expect("Could not parse program");
+
+    assert_eq!(run(&program), Ok(124))
+}
+
+#[test]
+fn test_arithmetic() {
+    let program = parse(include_str!("../../tests/arithmetic.kw")).expect("Could not parse program");
+
+    assert_eq!(run(&program), Ok(111))
+}
+
+#[test]
+fn test_division_by_zero() {
+    let program = parse("
+        function main() -> int {
+            var zero: int = 0;
+            return 1 / zero;
+        }
+    ").expect("Could not parse program");
+
+    run(&program).expect_err("Division by zero should be a runtime error");
+}
+
+#[test]
+fn test_logic() {
+    let program = parse(include_str!("../../tests/logic.kw")).expect("Could not parse program");
+
+    assert_eq!(run(&program), Ok(11))
+}
+
+#[test]
+fn test_unary() {
+    let program = parse("
+        function main() -> int {
+            var x: int = 3;
+            var y: int = x -1;
+            return -(x + y) - -2 + +1;
+        }
+    ").expect("Could not parse program");
+
+    assert_eq!(run(&program), Ok(-2))
+}
+
+#[test]
+fn test_callee_can_not_read_caller_locals() {
+    // x and y both get the first slot of their frame
+    let program = parse("
+        function main() -> int {
+            var x: int = 5;
+            return read_y() * 10 + x;
+        }
+
+        function read_y() -> int {
+            var y: int = 9;
+            return y;
+        }
+    ").expect("Could not parse program");
+
+    assert_eq!(run(&program), Ok(95), "A function should see its own variable in the slot, not the caller's");
+}
+
+#[test]
+fn test_callee_can_not_assign_caller_locals() {
+    // x and y both get the first slot of their frame
+    let program = parse("
+        function main() -> int {
+            var x: int = 5;
+            set_y();
+            return x;
+        }
+
+        function set_y() {
+            var y: int = 1;
+            y = 0;
+        }
+    ").expect("Could not parse program");
+
+    assert_eq!(run(&program), Ok(5), "A function should not be able to assign the local variables of its caller");
+}
+
+#[test]
+fn test_callee_names_do_not_clash_with_caller() {
+    let program = parse("
+        function main() -> int {
+            var x: int = 5;
+            var y: int = 7;
+            return x + y + change(1);
+        }
+
+        function change(x: int) -> int {
+            x = x + 10;
+            var y: int = 100;
+            return x + y;
+        }
+    ").expect("Could not parse program");
+
+    assert_eq!(run(&program), Ok(5 + 7 + 111))
+}
+
+#[test]
+fn test_recursion() {
+    let program = parse("
+        function main() -> int {
+            return fibonacci(10);
+        }
+
+        function fibonacci(n: int) -> int {
+            if (n < 2) {
+                return n;
+            }
+
+            var a: int = fibonacci(n - 1);
+            var b: int = fibonacci(n - 2);
+            return a + b;
+        }
+    ").expect("Could not parse program");
+
+    assert_eq!(run(&program), Ok(55))
+}
+
+#[test]
+fn test_error_location() {
+    let program = parse("
+        function main() -> int {
+            return double(2, 0);
+        }
+
+        function double(x: int, zero: int) -> int {
+            var y: int = 2 * x;
+            return y / zero;
+        }
+    ").expect("Could not parse program");
+
+    let error = run(&program).expect_err("Division by zero should be a runtime error");
+
+    assert_eq!(error.kind, ErrorKind::DivisionByZero);
+    let span = error.span.expect("The error should have a location");
+    assert_eq!((span.line, span.column), (8, 20));
+    assert_eq!(error.backtrace, vec!["double".to_string(), "main".to_string()]);
+}
+
+#[test]
+fn test_call_error_location() {
+    let program = parse("
+        function main() -> int {
+            return double(2, 0);
+        }
+
+        function double(x: int) -> int {
+            return 2 * x;
+        }
+    ").expect("Could not parse program");
+
+    let error = run(&program).expect_err("Calling with too many arguments should be a runtime error");
+
+    assert_eq!(error.kind, ErrorKind::Arity { function: "double".into(), expected: 1, found: 2 });
+    let span = error.span.expect("The error should have a location");
+    assert_eq!((span.line, span.column), (3, 20));
+    assert_eq!(error.backtrace, vec!["main".to_string()]);
+}
+
+#[test]
+fn test_runtime_errors() {
+    let cases = [
+        ("var x: int = true; return x;", ErrorKind::TypeMismatch { context: "variable x".into(), expected: Type::Int, found: Type::Bool }),
+        ("var x: int = 1; x = \"s\"; return x;", ErrorKind::TypeMismatch { context: "assignment to x".into(), expected: Type::Int, found: Type::String }),
+        ("if (1) { return 1; } return 0;", ErrorKind::InvalidCondition { statement: "if-statement", found: Type::Int }),
+        ("while (\"s\") { return 1; } return 0;", ErrorKind::InvalidCondition { statement: "while-loop", found: Type::String }),
+        ("return 1 + true;", ErrorKind::InvalidOperands { operator: "+", lhs: Type::Int, rhs: Type::Bool }),
+        ("return -true;", ErrorKind::InvalidOperand { operator: "-", operand: Type::Bool }),
+        ("if (true && 1 == 2 || 1) { return 1; } return 0;", ErrorKind::TypeMismatch { context: "right operand of ||".into(), expected: Type::Bool, found: Type::Int }),
+        ("if (1 && true) { return 1; } return 0;", ErrorKind::TypeMismatch { context: "left operand of &&".into(), expected: Type::Bool, found: Type::Int }),
+        ("return id(true);", ErrorKind::TypeMismatch { context: "parameter x of function id".into(), expected: Type::Int, found: Type::Bool }),
+        ("return id(1, 2);", ErrorKind::Arity { function: "id".into(), expected: 1, found: 2 }),
+        ("return nothing();", ErrorKind::NoReturnValue("nothing".into())),
+        ("nothing(); return wrong();", ErrorKind::TypeMismatch { context: "return value of function wrong".into(), expected: Type::Int, found: Type::String }),
+        ("return missing();", ErrorKind::MissingReturn("missing".into())),
+        ("unexpected(); return 0;", ErrorKind::UnexpectedReturnValue("unexpected".into())),
+        ("if (true) { break; } return 0;", ErrorKind::BreakOutsideLoop),
+        ("return 1 % 0;", ErrorKind::DivisionByZero),
+    ];
+
+    for (body, kind) in cases {
+        let program = parse(&format!("
+            function main() -> int {{ {body} }}
+            function id(x: int) -> int {{ return x; }}
+            function nothing() {{ }}
+            function wrong() -> int {{ return \"s\"; }}
+            function missing() -> int {{ }}
+            function unexpected() {{ return 1; }}
+        ")).expect("Could not parse program");
+
+        assert_eq!(run(&program).map_err(|error| error.kind), Err(kind), "{body}");
+    }
+}
+
+#[test]
+fn test_print() {
+    let program = parse("
+        function main() -> int {
+            print(\"fib:\");
+            var i: int = 0;
+            while (i < 5) {
+                print(\" \");
+                print(fibonacci(i));
+                i = i + 1;
+            }
+            println(\"\");
+            println(i == 5);
+            println(-3);
+            return 0;
+        }
+
+        function fibonacci(n: int) -> int {
+            if (n < 2) {
+                return n;
+            }
+            return fibonacci(n - 1) + fibonacci(n - 2);
+        }
+    ").expect("Could not parse program");
+
+    assert_eq!(run_with_output(&program), (Ok(0), "fib: 0 1 1 2 3\ntrue\n-3\n".to_string()));
+}
+
+#[test]
+fn test_print_errors() {
+    let cases = [
+        ("print(); return 0;", ErrorKind::Arity { function: "print".into(), expected: 1, found: 0 }),
+        ("var x: int = println(1); return x;", ErrorKind::NoReturnValue("println".into())),
+    ];
+
+    for (body, kind) in cases {
+        let program = parse(&format!("function main() -> int {{ {body} }}")).expect("Could not parse program");
+
+        assert_eq!(run(&program).map_err(|error| error.kind), Err(kind), "{body}");
+    }
+}
+
+#[test]
+fn test_strings() {
+    let program = parse("
+        function main() -> int {
+            var line: string = \"  name=Zoë Ünal  \";
+            var value: string = split(trim(line), \"=\", 1);
+            println(to_upper(value));
+            println(substring(value, index_of(value, \" \") + 1, len(value)));
+            println(repeat(\"-\", len(value)));
+            if (starts_with(value, \"Zo\") && !contains(value, \"=\")) {
+                return len(line);
+            }
+            return -1;
+        }
+    ").expect("Could not parse program");
+
+    assert_eq!(run_with_output(&program), (Ok(17), "ZOË ÜNAL\nÜnal\n--------\n".to_string()));
+}
+
+#[test]
+fn test_string_errors() {
+    let program = parse("
+        function main() -> int {
+            var s: string = \"héllo\";
+            return len(tail(s, 6));
+        }
+
+        function tail(s: string, start: int) -> string {
+            return substring(s, start, len(s));
+        }
+    ").expect("Could not parse program");
+
+    let error = run(&program).expect_err("The index should be out of range");
+    assert_eq!(error.kind, ErrorKind::IndexOutOfRange { index: 6, length: 5 });
+    assert_eq!(error.kind.to_string(), "Index 6 is out of range for length 5");
+    let span = error.span.expect("The error should have a location");
+    assert_eq!((span.line, span.column), (8, 20));
+    assert_eq!(error.backtrace, vec!["tail".to_string(), "main".to_string()]);
+
+    let program = parse("function main() -> int { return len(repeat(\"ab\", 4611686018427387904)); }")
+        .expect("Could not parse program");
+    let unlimited = Limits { max_string_bytes: None, ..Limits::default() };
+    assert_eq!(super::run_with_limits(&program, unlimited).map_err(|error| error.kind), Err(ErrorKind::IntegerOverflow));
+    assert_eq!(run(&program).map_err(|error| error.kind), Err(ErrorKind::StringLimit(Limits::default().max_string_bytes.unwrap())));
+}
+
+/// Resolve and run the program with the limits on both backends, checking that they agree.
+fn run_limited(source: &str, limits: Limits) -> Result<i64, RuntimeError> {
+    let mut program = parse(source).expect("Could not parse program");
+    resolve(&mut program).expect("Could not resolve program");
+
+    let result = super::run_with_limits(&program, limits.clone());
+
+    let module = bytecode::compile(&program).expect("Could not compile program to bytecode");
+    let bytecode_result = bytecode::execute_with_limits(&module, limits);
+
+    assert_eq!(bytecode_result, result);
+
+    result
+}
+
+#[test]
+fn test_statement_limit() {
+    let limits = Limits { max_statements: Some(100), ..Limits::default() };
+
+    let error = run_limited("
+        function main() -> int {
+            while (true) {}
+        }
+    ", limits.clone()).expect_err("An endless loop should run out of statements");
+
+    assert_eq!(error.kind, ErrorKind::StatementLimit(100));
+    let span = error.span.expect("The error should have a location");
+    assert_eq!((span.line, span.column), (3, 20));
+
+    let program = "
+        function main() -> int {
+            var i: int = 0;
+            while (i < 10) {
+                i = i + 1;
+            }
+            return i;
+        }
+    ";
+
+    assert_eq!(run_limited(program, limits), Ok(10));
+    assert_eq!(
+        run_limited(program, Limits { max_statements: Some(20), ..Limits::default() }).map_err(|error| error.kind),
+        Err(ErrorKind::StatementLimit(20)),
+    );
+}
+
+#[test]
+fn test_call_depth_limit() {
+    let program = "
+        function main() -> int {
+            return down(10);
+        }
+
+        function down(n: int) -> int {
+            if (n == 0) {
+                return 0;
+            }
+            return down(n - 1);
+        }
+    ";
+
+    assert_eq!(run_limited(program, Limits { max_call_depth: 12, ..Limits::default() }), Ok(0));
+
+    let error = run_limited(program, Limits { max_call_depth: 11, ..Limits::default() })
+        .expect_err("The recursion should be too deep");
+
+    assert_eq!(error.kind, ErrorKind::CallDepthLimit(11));
+    let span = error.span.expect("The error should have a location");
+    assert_eq!((span.line, span.column), (10, 20));
+    assert_eq!(error.backtrace.len(), 11);
+
+    // Without an explicit limit, endless recursion stops before the stack overflows
+    let error = run_limited("
+        function main() -> int {
+            return main();
+        }
+    ", Limits::default()).expect_err("Endless recursion should be an error");
+
+    assert_eq!(error.kind, ErrorKind::CallDepthLimit(Limits::default().max_call_depth));
+}
+
+#[test]
+fn test_stack() {
+    // Deep recursion through nested blocks and expressions fits in the stack
+    let program = "
+        function main() -> int {
+            return f(995);
+        }
+
+        function f(n: int) -> int {
+            if (n > 0) {
+                if (n > -1) {
+                    if (n > -2) {
+                        return 1 + (1 + (1 + (1 + f(n - 1))));
+                    }
+                }
+            }
+            return 0;
+        }
+    ";
+
+    assert_eq!(run_limited(program, Limits::default()), Ok(3980));
+
+    let depth = 50;
+    let nested = format!(
+        "function main() -> int {{ {} return 0; {} }}",
+        "if (true) {".repeat(depth),
+        "}".repeat(depth),
+    );
+
+    assert_eq!(run_limited(&nested, Limits::default()), Ok(0));
+
+    let mut program = parse(program).expect("Could not parse program");
+    resolve(&mut program).expect("Could not resolve program");
+
+    // A stack that can't be allocated is an error, not a panic
+    let error = super::run_with_limits(&program, Limits { max_call_depth: usize::MAX, ..Limits::default() })
+        .expect_err("The stack should be too large");
+    assert!(matches!(error.kind, ErrorKind::Thread(_)), "{error}");
+}
+
+#[test]
+fn test_string_limit() {
+    let program = "
+        function main() -> int {
+            var s: string = \"ab\";
+            while (true) {
+                s = s + s;
+            }
+        }
+    ";
+
+    let error = run_limited(program, Limits { max_string_bytes: Some(1000), ..Limits::default() })
+        .expect_err("The string should grow too large");
+
+    assert_eq!(error.kind, ErrorKind::StringLimit(1000));
+    let span = error.span.expect("The error should have a location");
+    assert_eq!((span.line, span.column), (5, 21));
+
+    // Literals don't count
+    assert_eq!(run_limited("
+        function main() -> int {
+            var s: string = \"a long string literal\";
+            return 0;
+        }
+    ", Limits { max_string_bytes: Some(0), ..Limits::default() }), Ok(0));
+
+    // The string functions are checked before building the string
+    let error = run_limited("
+        function main() -> int {
+            return len(repeat(\"ab\", 1000000000));
+        }
+    ", Limits { max_string_bytes: Some(1000), ..Limits::default() }).expect_err("The string should be too large");
+    assert_eq!(error.kind, ErrorKind::StringLimit(1000));
+}
+
+#[test]
+fn test_conversions() {
+    let program = parse("
+        function main() -> int {
+            var total: int = parse_int(\"-12\") + parse_int(\"+30\");
+            println(\"total: \" + to_string(total));
+            println(to_string(parse_bool(\"true\")) + \" \" + to_string(\"text\"));
+            return parse_int(to_string(total)) + len(to_string(-100));
+        }
+    ").expect("Could not parse program");
+
+    assert_eq!(run_with_output(&program), (Ok(22), "total: 18\ntrue text\n".to_string()));
+
+    let cases = [
+        ("return parse_int(\"12a\");", ErrorKind::Parse { text: "12a".into(), expected: Type::Int }),
+        ("return parse_int(\"99999999999999999999\");", ErrorKind::Parse { text: "99999999999999999999".into(), expected: Type::Int }),
+        ("if (parse_bool(\"yes\")) {} return 0;", ErrorKind::Parse { text: "yes".into(), expected: Type::Bool }),
+    ];
+
+    for (body, kind) in cases {
+        let program = parse(&format!("function main() -> int {{ {body} }}")).expect("Could not parse program");
+
+        assert_eq!(run(&program).map_err(|error| error.kind), Err(kind), "{body}");
+    }
+}
+
+#[test]
+fn test_integer_overflow() {
+    let program = parse("
+        function main() -> int {
+            var big: int = 4611686018427387904;
+            println(big + (big - 1));
+            println(big *% 2);
+            println(-9223372036854775807 -% 2);
+            println(9223372036854775807 +% 1 == -9223372036854775808);
+            return big / 1000000000000;
+        }
+    ").expect("Could not parse program");
+
+    assert_eq!(
+        run_with_output(&program),
+        (Ok(4611686), "9223372036854775807\n-9223372036854775808\n9223372036854775807\ntrue\n".to_string()),
+    );
+
+    let cases = [
+        "return 9223372036854775807 + 1;",
+        "return -9223372036854775807 - 2;",
+        "return 4611686018427387904 * 2;",
+        "var x: int = -9223372036854775807 - 1; return x / -1;",
+        "var x: int = -9223372036854775807 - 1; return -x;",
+    ];
+
+    for body in cases {
+        let program = parse(&format!("function main() -> int {{ {body} }}")).expect("Could not parse program");
+
+        let error = run(&program).expect_err("The operation should overflow");
+        assert_eq!(error.kind, ErrorKind::IntegerOverflow, "{body}");
+        assert!(error.span.is_some(), "{body}");
+    }
+}
+
+/// Compare the speed of the backends. Run with
+/// `cargo test --release bench_fibonacci -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn bench_fibonacci() {
+    let mut program = parse(include_str!("../../tests/fibonacci.kw")).expect("Could not parse program");
+    resolve(&mut program).expect("Could not resolve program");
+
+    let start = std::time::Instant::now();
+    assert_eq!(super::run(&program), Ok(75025));
+    println!("Tree-walker: {:?}", start.elapsed());
+
+    let start = std::time::Instant::now();
+    let module = bytecode::compile(&program).expect("Could not compile program to bytecode");
+    assert_eq!(bytecode::execute(&module), Ok(75025));
+    println!("Bytecode: {:?}", start.elapsed());
+}
@@ -1,11 +1,15 @@
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
-use std::ops::{Add, Sub};
-use crate::parser::ast::Type;
+use std::sync::Arc;
+use std::ops::{Add, Div, Mul, Neg, Not, Rem, Sub};
+use crate::parser::ast::{BinaryVerb, Type, UnaryVerb};
+use super::error::ErrorKind;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Value {
-    Int(i32),
-    String(String),
+    Int(i64),
+    /// Strings are immutable, so they can be shared instead of copied.
+    String(Arc<str>),
     Bool(bool),
 }
 
@@ -18,53 +22,187 @@ impl Value {
         }
     }
 
-    pub fn compare(&self, other: &Self) -> Result<Value, String> {
+    pub fn compare(&self, other: &Self) -> Result<Value, ErrorKind> {
         match (&self, &other) {
             (Value::Int(x), Value::Int(y)) => Ok(Value::Bool(x==y)),
             (Value::String(x), Value::String(y)) => Ok(Value::Bool(x==y)),
-            (Value::Bool(x), Value::Bool(y)) => Ok(Value::Bool(*x && *y)),
-            _ => Err(format!("Cannot compare {} and {}", self, other))
+            (Value::Bool(x), Value::Bool(y)) => Ok(Value::Bool(x==y)),
+            _ => Err(ErrorKind::InvalidOperands { operator: "==", lhs: self.ast_type(), rhs: other.ast_type() })
         }
     }
+
+    /// Order two values, for the `<`, `<=`, `>` and `>=` operators.
+    ///
+    /// Only integers can be ordered.
+    pub fn order(&self, other: &Self) -> Option<Ordering> {
+        match (&self, &other) {
+            (Value::Int(x), Value::Int(y)) => Some(x.cmp(y)),
+            _ => None,
+        }
+    }
+}
+
+/// Apply a binary operator, except the logical operators which are short-circuited.
+pub fn binary_operation(verb: &BinaryVerb, lhs: Value, rhs: Value) -> Result<Value, ErrorKind> {
+    let invalid_operands = ErrorKind::InvalidOperands {
+        operator: verb.symbol(),
+        lhs: lhs.ast_type(),
+        rhs: rhs.ast_type(),
+    };
+
+    if lhs.ast_type() != rhs.ast_type() {
+        return Err(invalid_operands);
+    }
+
+    match verb {
+        BinaryVerb::Plus => lhs + rhs,
+        BinaryVerb::Minus => lhs - rhs,
+        BinaryVerb::Multiply => lhs * rhs,
+        BinaryVerb::Divide => lhs / rhs,
+        BinaryVerb::Modulo => lhs % rhs,
+        BinaryVerb::WrappingPlus | BinaryVerb::WrappingMinus | BinaryVerb::WrappingMultiply => match (lhs, rhs) {
+            (Value::Int(x), Value::Int(y)) => Ok(Value::Int(match verb {
+                BinaryVerb::WrappingPlus => x.wrapping_add(y),
+                BinaryVerb::WrappingMinus => x.wrapping_sub(y),
+                _ => x.wrapping_mul(y),
+            })),
+            _ => Err(invalid_operands),
+        },
+        BinaryVerb::Compare => lhs.compare(&rhs),
+        BinaryVerb::NotEqual => match lhs.compare(&rhs)? {
+            Value::Bool(equal) => Ok(Value::Bool(!equal)),
+            _ => unreachable!("Comparisons always result in a bool"),
+        },
+        BinaryVerb::Less => lhs.order(&rhs).map(|o| Value::Bool(o.is_lt())).ok_or(invalid_operands),
+        BinaryVerb::LessEqual => lhs.order(&rhs).map(|o| Value::Bool(o.is_le())).ok_or(invalid_operands),
+        BinaryVerb::Greater => lhs.order(&rhs).map(|o| Value::Bool(o.is_gt())).ok_or(invalid_operands),
+        BinaryVerb::GreaterEqual => lhs.order(&rhs).map(|o| Value::Bool(o.is_ge())).ok_or(invalid_operands),
+        BinaryVerb::And | BinaryVerb::Or => unreachable!("Logical operators are short-circuited"),
+    }
+}
+
+/// Check that an operand of `&&` or `||` is a bool.
+pub fn logic_operand(verb: &BinaryVerb, side: &str, value: Value) -> Result<bool, ErrorKind> {
+    match value {
+        Value::Bool(x) => Ok(x),
+        value => Err(ErrorKind::TypeMismatch {
+            context: format!("{side} operand of {}", verb.symbol()),
+            expected: Type::Bool,
+            found: value.ast_type(),
+        }),
+    }
+}
+
+pub fn unary_operation(op: &UnaryVerb, operand: Value) -> Result<Value, ErrorKind> {
+    match (op, operand) {
+        (UnaryVerb::Plus, operand @ Value::Int(_)) => Ok(operand),
+        (UnaryVerb::Plus, operand) => Err(ErrorKind::InvalidOperand { operator: op.symbol(), operand: operand.ast_type() }),
+        (UnaryVerb::Minus, operand) => -operand,
+        (UnaryVerb::Not, operand) => !operand,
+    }
 }
 
 impl Add for Value {
-    type Output = Result<Value, String>;
+    type Output = Result<Value, ErrorKind>;
 
     fn add(self, rhs: Self) -> Self::Output {
         match (&self, &rhs) {
-            (Value::Int(x), Value::Int(y)) => Ok(Value::Int(x + y)),
-            (Value::String(x), Value::String(y)) => Ok(Value::String(x.clone() + y)),
-            (Value::Bool(x), Value::Bool(y)) => Ok(Value::Bool(*x && *y)),
-            _ => Err(format!("Cannot add {} and {}", self, rhs))
+            (Value::Int(x), Value::Int(y)) => x.checked_add(*y)
+                .map(Value::Int)
+                .ok_or(ErrorKind::IntegerOverflow),
+            (Value::String(x), Value::String(y)) => Ok(Value::String(format!("{x}{y}").into())),
+            _ => Err(ErrorKind::InvalidOperands { operator: "+", lhs: self.ast_type(), rhs: rhs.ast_type() })
         }
     }
 }
 
 impl Sub for Value {
-    type Output = Result<Value, String>;
+    type Output = Result<Value, ErrorKind>;
 
     fn sub(self, rhs: Self) -> Self::Output {
         match (&self, &rhs) {
-            (Value::Int(x), Value::Int(y)) => Ok(Value::Int(x-y)),
+            (Value::Int(x), Value::Int(y)) => x.checked_sub(*y)
+                .map(Value::Int)
+                .ok_or(ErrorKind::IntegerOverflow),
             // (Value::String(x), Value::String(y)) => Ok(Value::String(x+y))
             // (Value::Bool(x), Value::Bool(y)) => Ok(Value::Bool(x+y))
-            _ => Err(format!("Cannot subtract {} and {}", self, rhs))
+            _ => Err(ErrorKind::InvalidOperands { operator: "-", lhs: self.ast_type(), rhs: rhs.ast_type() })
+        }
+    }
+}
+
+impl Mul for Value {
+    type Output = Result<Value, ErrorKind>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        match (&self, &rhs) {
+            (Value::Int(x), Value::Int(y)) => x.checked_mul(*y)
+                .map(Value::Int)
+                .ok_or(ErrorKind::IntegerOverflow),
+            _ => Err(ErrorKind::InvalidOperands { operator: "*", lhs: self.ast_type(), rhs: rhs.ast_type() })
+        }
+    }
+}
+
+impl Div for Value {
+    type Output = Result<Value, ErrorKind>;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        match (&self, &rhs) {
+            (Value::Int(_), Value::Int(0)) => Err(ErrorKind::DivisionByZero),
+            (Value::Int(x), Value::Int(y)) => x.checked_div(*y)
+                .map(Value::Int)
+                .ok_or(ErrorKind::IntegerOverflow),
+            _ => Err(ErrorKind::InvalidOperands { operator: "/", lhs: self.ast_type(), rhs: rhs.ast_type() })
+        }
+    }
+}
+
+impl Rem for Value {
+    type Output = Result<Value, ErrorKind>;
+
+    fn rem(self, rhs: Self) -> Self::Output {
+        match (&self, &rhs) {
+            (Value::Int(_), Value::Int(0)) => Err(ErrorKind::DivisionByZero),
+            (Value::Int(x), Value::Int(y)) => x.checked_rem(*y)
+                .map(Value::Int)
+                .ok_or(ErrorKind::IntegerOverflow),
+            _ => Err(ErrorKind::InvalidOperands { operator: "%", lhs: self.ast_type(), rhs: rhs.ast_type() })
+        }
+    }
+}
+
+impl Neg for Value {
+    type Output = Result<Value, ErrorKind>;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            Value::Int(x) => x.checked_neg()
+                .map(Value::Int)
+                .ok_or(ErrorKind::IntegerOverflow),
+            _ => Err(ErrorKind::InvalidOperand { operator: "-", operand: self.ast_type() })
+        }
+    }
+}
+
+impl Not for Value {
+    type Output = Result<Value, ErrorKind>;
+
+    fn not(self) -> Self::Output {
+        match self {
+            Value::Bool(x) => Ok(Value::Bool(!x)),
+            _ => Err(ErrorKind::InvalidOperand { operator: "!", operand: self.ast_type() })
         }
     }
 }
 
+/// Values are shown as `print` writes them.
 impl Display for Value {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}({})",
-            self.ast_type(),
-            match self {
-                Self::String(x) => x.to_string(),
-                Self::Int(x) => x.to_string(),
-                Self::Bool(x) => x.to_string(),
-            }
-        )
+        match self {
+            Self::String(x) => write!(f, "{x}"),
+            Self::Int(x) => write!(f, "{x}"),
+            Self::Bool(x) => write!(f, "{x}"),
+        }
     }
 }